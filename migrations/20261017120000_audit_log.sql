@@ -0,0 +1,11 @@
+create table if not exists "audit_log" (
+	"id" integer not null primary key autoincrement,
+	"created_at" bigint not null default (unixepoch()),
+	"actor" text not null,
+	"action" text not null,
+	"target" bigint,
+	"before" text,
+	"after" text
+);
+create index if not exists "audit_log_target" on "audit_log" ("target");
+create index if not exists "audit_log_actor" on "audit_log" ("actor")
@@ -0,0 +1,4 @@
+alter table "members" add column "verified_at" bigint;
+alter table "members" add column "method" varchar(16) not null default 'unknown'
+	check ("method" in ('unknown', 'login', 'membership', 'manual', 'admin'));
+alter table "members" add column "verified_by" bigint
@@ -0,0 +1,10 @@
+create table if not exists "membership_years" (
+	"discord_id" bigint not null,
+	"year" integer not null,
+	"fresher" varchar(16) not null,
+	primary key ("discord_id", "year"),
+	check ("fresher" in ('no', 'yes_pg', 'yes_ug'))
+);
+insert or ignore into "membership_years"
+	select "discord_id", cast(strftime('%Y', 'now', '-7 months') as integer), "fresher"
+	from "members"
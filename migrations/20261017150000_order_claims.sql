@@ -0,0 +1,7 @@
+create table if not exists "order_claims" (
+	"order_no" bigint not null primary key,
+	"discord_id" bigint not null,
+	"shortcode" text not null,
+	"claimed_at" bigint not null default (unixepoch())
+);
+create index if not exists "order_claims_discord_id" on "order_claims" ("discord_id")
@@ -0,0 +1,9 @@
+create table if not exists "verify_attempts" (
+	"discord_id" bigint not null,
+	"flow" varchar(16) not null,
+	"failures" integer not null default 0,
+	"window_start" bigint not null default (unixepoch()),
+	"locked_until" bigint,
+	primary key ("discord_id", "flow"),
+	check ("flow" in ('membership', 'manual'))
+)
@@ -0,0 +1,6 @@
+create table if not exists "login_states" (
+	"nonce" text not null primary key,
+	"discord_id" bigint not null,
+	"expires_at" bigint not null,
+	"used_at" bigint
+)
@@ -0,0 +1,6 @@
+create table if not exists "api_keys" (
+	"name" text not null primary key,
+	"key_hash" text not null unique,
+	"scopes" text not null,
+	"created_at" bigint not null default (unixepoch())
+)
@@ -0,0 +1,14 @@
+create table if not exists "webhook_deliveries" (
+	"id" integer not null primary key autoincrement,
+	"endpoint" text not null,
+	"event" text not null,
+	"payload" text not null,
+	"status" text not null default 'pending' check ("status" in ('pending', 'delivered', 'failed')),
+	"attempts" bigint not null default 0,
+	"next_attempt_at" bigint not null default (unixepoch()),
+	"last_error" text,
+	"created_at" bigint not null default (unixepoch()),
+	"delivered_at" bigint
+);
+
+create index if not exists "webhook_deliveries_due" on "webhook_deliveries" ("status", "next_attempt_at");
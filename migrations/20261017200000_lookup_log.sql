@@ -0,0 +1,9 @@
+create table if not exists "lookup_log" (
+	"id" integer not null primary key autoincrement,
+	"created_at" bigint not null default (unixepoch()),
+	"client" text not null,
+	"discord_id" bigint not null,
+	"verified" boolean not null
+);
+create index if not exists "lookup_log_client" on "lookup_log" ("client");
+create index if not exists "lookup_log_discord_id" on "lookup_log" ("discord_id")
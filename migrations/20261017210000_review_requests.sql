@@ -0,0 +1,7 @@
+create table if not exists "review_requests" (
+	"discord_id" bigint not null primary key references "manual" ("discord_id") on delete cascade,
+	"url" text not null,
+	"channel_id" bigint,
+	"message_id" bigint,
+	"created_at" bigint not null default (unixepoch())
+)
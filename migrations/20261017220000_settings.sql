@@ -0,0 +1,5 @@
+create table if not exists "settings" (
+	"key" text not null primary key,
+	"value" bigint not null,
+	"updated_at" bigint not null default (unixepoch())
+)
@@ -0,0 +1,5 @@
+create table if not exists "templates" (
+	"key" text not null primary key,
+	"body" text not null,
+	"updated_at" bigint not null default (unixepoch())
+)
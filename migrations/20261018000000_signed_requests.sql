@@ -0,0 +1,7 @@
+create table if not exists "signed_requests" (
+	"client" text not null,
+	"signature" text not null,
+	"expires_at" bigint not null,
+	primary key ("client", "signature")
+);
+create index if not exists "signed_requests_expires_at" on "signed_requests" ("expires_at")
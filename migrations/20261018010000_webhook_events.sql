@@ -0,0 +1,6 @@
+create table if not exists "webhook_events" (
+	"id" integer not null primary key autoincrement,
+	"event" text not null,
+	"payload" text not null,
+	"created_at" bigint not null default (unixepoch())
+)
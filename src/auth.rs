@@ -0,0 +1,279 @@
+use crate::{db, ApiKey, Error};
+use axum::http::{HeaderMap, StatusCode};
+use hmac::{Hmac, Mac};
+use poise::serenity_prelude as serenity;
+use sha2::{Digest, Sha256};
+
+/// Maximum age (and clock skew) in seconds of a signed request's timestamp
+const REPLAY_WINDOW: i64 = 300;
+
+/// Permission granted to an API key
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Scope {
+    Export,
+    Import,
+    Verify,
+    /// Read-only membership lookups
+    Lookup,
+    /// Web dashboard for reviewing manual verification requests
+    Review,
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Scope::Export => write!(f, "export"),
+            Scope::Import => write!(f, "import"),
+            Scope::Verify => write!(f, "verify"),
+            Scope::Lookup => write!(f, "lookup"),
+            Scope::Review => write!(f, "review"),
+        }
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "export" => Ok(Self::Export),
+            "import" => Ok(Self::Import),
+            "verify" => Ok(Self::Verify),
+            "lookup" => Ok(Self::Lookup),
+            "review" => Ok(Self::Review),
+            _ => Err(format!("Unknown scope: {s}")),
+        }
+    }
+}
+
+/// Generate a new random API key
+pub(crate) fn generate_key() -> String {
+    format!("nano_{}", hex::encode(rand::random::<[u8; 32]>()))
+}
+
+/// Hash API key for storage
+pub(crate) fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Check if API key has been granted `scope`
+pub(crate) fn has_scope(key: &ApiKey, scope: Scope) -> bool {
+    key.scopes.split(',').any(|s| s.parse() == Ok(scope))
+}
+
+/// Shared secrets from before header auth, only accepted if `LEGACY_KEY_AUTH` is set
+#[derive(Clone)]
+struct LegacyKeys {
+    export: String,
+    import: String,
+    verify: String,
+}
+
+/// Authenticates HTTP API requests using one of:
+/// - `Authorization: Bearer <key>`
+/// - `X-Nano-Key: <name>`, `X-Nano-Timestamp: <unix time>` and
+///   `X-Nano-Signature: <hex HMAC-SHA256 of "<timestamp>.<body>">`, keyed with the
+///   key's signing secret, each signature only accepted once
+/// - (deprecated) `key` in the request body or query
+#[derive(Clone)]
+pub(crate) struct Auth {
+    pool: sqlx::SqlitePool,
+    /// Server secret that signing secrets and dashboard sessions are derived from, never stored
+    secret: String,
+    legacy: Option<LegacyKeys>,
+}
+
+impl Auth {
+    pub(crate) fn from_env(pool: sqlx::SqlitePool) -> Result<Self, Error> {
+        use crate::var;
+        use anyhow::Context as _;
+
+        let legacy = std::env::var("LEGACY_KEY_AUTH").is_ok_and(|v| v == "true");
+        let legacy = if legacy {
+            tracing::warn!("LEGACY_KEY_AUTH is deprecated, move API clients to header auth");
+            Some(LegacyKeys {
+                export: var!("EXPORT_KEY"),
+                import: var!("IMPORT_KEY"),
+                verify: var!("VERIFY_KEY"),
+            })
+        } else {
+            None
+        };
+        Ok(Self {
+            pool,
+            secret: var!("API_SECRET"),
+            legacy,
+        })
+    }
+
+    /// HMAC-SHA256 keyed with the server secret, used to derive secrets and sign sessions
+    pub(crate) fn mac(&self, purpose: &str, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(purpose.as_bytes());
+        mac.update(b".");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    /// Secret for signing requests with the key of hash `key_hash`
+    ///
+    /// Derived from the server secret, so the stored hash alone cannot sign requests
+    pub(crate) fn signing_secret(&self, key_hash: &str) -> String {
+        hex::encode(self.mac("signing", key_hash).finalize().into_bytes())
+    }
+
+    /// Check request is authorised for `scope`, returning the client name
+    pub(crate) async fn check(
+        &self,
+        scope: Scope,
+        headers: &HeaderMap,
+        body: &[u8],
+        legacy_key: Option<&str>,
+    ) -> Result<String, StatusCode> {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+        let key =
+            if let Some(bearer) = header("authorization").and_then(|a| a.strip_prefix("Bearer ")) {
+                db::get_api_key_by_hash(&self.pool, &hash_key(bearer.trim())).await
+            } else if let (Some(name), Some(timestamp), Some(signature)) = (
+                header("x-nano-key"),
+                header("x-nano-timestamp"),
+                header("x-nano-signature"),
+            ) {
+                let now = serenity::Timestamp::now().unix_timestamp();
+                let Some(sent) = timestamp
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|t| (now - t).abs() <= REPLAY_WINDOW)
+                else {
+                    tracing::warn!("Rejected signed request from {name}: outside replay window");
+                    return Err(StatusCode::UNAUTHORIZED);
+                };
+                let Ok(signature) = hex::decode(signature) else {
+                    return Err(StatusCode::UNAUTHORIZED);
+                };
+                match db::get_api_key_by_name(&self.pool, name).await {
+                    Ok(Some(key)) => {
+                        let secret = self.signing_secret(&key.key_hash);
+                        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                            .expect("HMAC accepts keys of any length");
+                        mac.update(timestamp.as_bytes());
+                        mac.update(b".");
+                        mac.update(body);
+                        if mac.verify_slice(&signature).is_err() {
+                            tracing::warn!("Rejected signed request from {name}: bad signature");
+                            return Err(StatusCode::UNAUTHORIZED);
+                        }
+                        // Signature can't be accepted again after the timestamp leaves the window
+                        match db::insert_signed_request(
+                            &self.pool,
+                            &key.name,
+                            &hex::encode(&signature),
+                            sent + REPLAY_WINDOW,
+                        )
+                        .await
+                        {
+                            Ok(true) => Ok(Some(key)),
+                            Ok(false) => {
+                                tracing::warn!("Rejected signed request from {name}: replayed");
+                                return Err(StatusCode::UNAUTHORIZED);
+                            }
+                            Err(e) => Err(e),
+                        }
+                    }
+                    other => other,
+                }
+            } else {
+                return self.check_legacy(scope, legacy_key);
+            };
+
+        match key {
+            Ok(Some(key)) => {
+                if has_scope(&key, scope) {
+                    Ok(key.name)
+                } else {
+                    tracing::warn!("Rejected request from {}: missing scope {scope}", key.name);
+                    Err(StatusCode::FORBIDDEN)
+                }
+            }
+            Ok(None) => Err(StatusCode::UNAUTHORIZED),
+            Err(e) => {
+                tracing::error!("{e}");
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    /// Check deprecated shared key, if enabled
+    fn check_legacy(&self, scope: Scope, key: Option<&str>) -> Result<String, StatusCode> {
+        let (Some(legacy), Some(key)) = (&self.legacy, key) else {
+            return Err(StatusCode::UNAUTHORIZED);
+        };
+        let expected = match scope {
+            Scope::Export => &legacy.export,
+            Scope::Import => &legacy.import,
+            Scope::Verify => &legacy.verify,
+            // Added after key auth, so have no legacy key
+            Scope::Lookup | Scope::Review => return Err(StatusCode::UNAUTHORIZED),
+        };
+        if key == expected {
+            tracing::warn!("Deprecated key auth used for {scope}");
+            Ok("legacy".to_string())
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+}
+
+/// Auth with a fixed server secret and no legacy keys
+#[cfg(test)]
+pub(crate) fn test_auth(pool: sqlx::SqlitePool) -> Auth {
+    Auth {
+        pool,
+        secret: "server secret".to_string(),
+        legacy: None,
+    }
+}
+
+/// Headers signing `body` now as client `name`, keyed with `secret`
+#[cfg(test)]
+pub(crate) fn signed_headers(name: &str, secret: &str, body: &[u8]) -> HeaderMap {
+    let timestamp = serenity::Timestamp::now().unix_timestamp().to_string();
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    let mut headers = HeaderMap::new();
+    headers.insert("x-nano-key", name.parse().unwrap());
+    headers.insert("x-nano-timestamp", timestamp.parse().unwrap());
+    let signature = hex::encode(mac.finalize().into_bytes());
+    headers.insert("x-nano-signature", signature.parse().unwrap());
+    headers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, Actor};
+
+    #[tokio::test]
+    async fn signed_requests_need_derived_secret_and_are_not_replayable() {
+        let pool = test_pool().await;
+        let hash = hash_key(&generate_key());
+        db::insert_api_key(&pool, Actor::System, "client", &hash, "export")
+            .await
+            .unwrap();
+        let auth = test_auth(pool);
+
+        let stored = signed_headers("client", &hash, b"{}");
+        let result = auth.check(Scope::Export, &stored, b"{}", None).await;
+        assert_eq!(result, Err(StatusCode::UNAUTHORIZED));
+
+        let headers = signed_headers("client", &auth.signing_secret(&hash), b"{}");
+        let result = auth.check(Scope::Export, &headers, b"{}", None).await;
+        assert_eq!(result, Ok("client".to_string()));
+        let replayed = auth.check(Scope::Export, &headers, b"{}", None).await;
+        assert_eq!(replayed, Err(StatusCode::UNAUTHORIZED));
+    }
+}
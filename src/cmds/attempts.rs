@@ -0,0 +1,17 @@
+use crate::{cmds::checks::committee, db, ACtx, Error};
+use poise::serenity_prelude as serenity;
+
+/// Reset a user's failed verification attempts and lift any lockout
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, check = "committee")]
+pub(crate) async fn reset_attempts(ctx: ACtx<'_>, user: serenity::User) -> Result<(), Error> {
+    tracing::info!("{} {}", ctx.author().name, user.name);
+    if db::reset_attempts(&ctx.data().db, ctx.into(), user.id.into()).await? {
+        ctx.say(format!("Reset verification attempts for {user}"))
+            .await?;
+    } else {
+        ctx.say(format!("No verification attempts found for {user}"))
+            .await?;
+    }
+    Ok(())
+}
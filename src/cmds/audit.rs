@@ -0,0 +1,99 @@
+use crate::{cmds::checks::committee, db, ACtx, Actor, AuditEntry, Error};
+use poise::serenity_prelude as serenity;
+use std::fmt::Write as _;
+
+const AUDIT_LIMIT: i64 = 100;
+const AUDIT_PAGE_SIZE: usize = 5;
+
+/// Format audit log entry for display
+fn format_entry(e: &AuditEntry) -> String {
+    let actor = e
+        .actor
+        .strip_prefix("user:")
+        .map_or(e.actor.clone(), |id| format!("<@{id}>"));
+    let target = e.target.map_or("-".to_string(), |id| format!("<@{id}>"));
+    let mut s = format!(
+        "`#{}` <t:{}:f> **{}** {target} by {actor}\n",
+        e.id, e.created_at, e.action
+    );
+    if let Some(before) = &e.before {
+        writeln!(s, "Before: `{before}`").expect("String write! is infallible");
+    }
+    if let Some(after) = &e.after {
+        writeln!(s, "After: `{after}`").expect("String write! is infallible");
+    }
+    s
+}
+
+/// Check date is a valid `YYYY-MM-DD`, as invalid dates match nothing in the query
+fn valid_date(date: &str) -> bool {
+    date.len() == 10 && serenity::Timestamp::parse(&format!("{date}T00:00:00Z")).is_ok()
+}
+
+/// Search the audit log of membership changes
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, check = "committee")]
+pub(crate) async fn audit(
+    ctx: ACtx<'_>,
+    #[description = "Member the change was made to"] member: Option<serenity::User>,
+    #[description = "User who made the change"] actor: Option<serenity::User>,
+    #[description = "Other actor who made the change, e.g. system, route:/verify or client:<key name>"]
+    other_actor: Option<String>,
+    #[description = "Changes on or after date (YYYY-MM-DD)"] since: Option<String>,
+    #[description = "Changes on or before date (YYYY-MM-DD)"] until: Option<String>,
+    #[description = "Changes older than entry number, to see past the newest results"]
+    before: Option<i64>,
+) -> Result<(), Error> {
+    tracing::info!("{}", ctx.author().name);
+    let actor = match (actor, other_actor) {
+        (Some(_), Some(_)) => {
+            ctx.say("Use only one of actor and other_actor").await?;
+            return Ok(());
+        }
+        (Some(u), None) => Some(Actor::User(u.id).to_string()),
+        (None, other) => other,
+    };
+    if let Some(date) = [&since, &until]
+        .into_iter()
+        .flatten()
+        .find(|d| !valid_date(d))
+    {
+        ctx.say(format!("Invalid date {date}, use YYYY-MM-DD"))
+            .await?;
+        return Ok(());
+    }
+    // Fetch one extra entry to tell if there are older results past the limit
+    let mut entries = db::get_audit(
+        &ctx.data().db,
+        member.map(|u| u.id.into()),
+        actor,
+        since,
+        until,
+        before,
+        AUDIT_LIMIT + 1,
+    )
+    .await?;
+    if entries.is_empty() {
+        ctx.say("No audit log entries found").await?;
+        return Ok(());
+    }
+    let truncated = i64::try_from(entries.len())? > AUDIT_LIMIT;
+    if truncated {
+        entries.pop();
+    }
+    let mut pages = entries
+        .chunks(AUDIT_PAGE_SIZE)
+        .map(|page| page.iter().map(format_entry).collect::<String>())
+        .collect::<Vec<_>>();
+    if let (true, Some(last), Some(oldest)) = (truncated, pages.last_mut(), entries.last()) {
+        write!(
+            last,
+            "Showing the newest {AUDIT_LIMIT} results, run again with before:{} for older",
+            oldest.id
+        )
+        .expect("String write! is infallible");
+    }
+    let pages = pages.iter().map(String::as_str).collect::<Vec<_>>();
+    poise::builtins::paginate(ctx.into(), &pages).await?;
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use crate::{Data, Error};
+use poise::{serenity_prelude as serenity, CreateReply};
+
+/// Check if roles include the committee role
+pub(crate) fn is_committee(data: &Data, roles: &[serenity::RoleId]) -> bool {
+    roles.contains(&data.committee)
+}
+
+/// Check if roles include the committee or (optional) reviewer role, who can run read-only
+/// commands and accept, deny or gaijin manual requests
+pub(crate) fn is_reviewer(data: &Data, roles: &[serenity::RoleId]) -> bool {
+    is_committee(data, roles) || data.reviewer.is_some_and(|r| roles.contains(&r))
+}
+
+/// Run role check against command author, replying with denial message on failure
+async fn require(
+    ctx: poise::Context<'_, Data, Error>,
+    allowed: fn(&Data, &[serenity::RoleId]) -> bool,
+    denial: &str,
+) -> Result<bool, Error> {
+    let permitted = ctx
+        .author_member()
+        .await
+        .is_some_and(|m| m.guild_id == ctx.data().guild().server && allowed(ctx.data(), &m.roles));
+    if !permitted {
+        tracing::warn!(
+            "{} ({}) denied /{}",
+            ctx.author().name,
+            ctx.author().id,
+            ctx.command().qualified_name
+        );
+        let reply = CreateReply::default().ephemeral(true).content(denial);
+        ctx.send(reply).await?;
+    }
+    Ok(permitted)
+}
+
+/// Poise check, allow only committee members
+pub(crate) async fn committee(ctx: poise::Context<'_, Data, Error>) -> Result<bool, Error> {
+    require(
+        ctx,
+        is_committee,
+        "Sorry, this command is restricted to committee members",
+    )
+    .await
+}
+
+/// Poise check, allow committee members and reviewers (read-only commands, reviewers can
+/// also action manual requests through the review buttons)
+pub(crate) async fn reviewer(ctx: poise::Context<'_, Data, Error>) -> Result<bool, Error> {
+    require(
+        ctx,
+        is_reviewer,
+        "Sorry, this command is restricted to committee members and reviewers",
+    )
+    .await
+}
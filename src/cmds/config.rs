@@ -0,0 +1,155 @@
+use crate::{
+    cmds::checks::committee,
+    config::{Key, Kind},
+    diagnose::Report,
+    ACtx, Error,
+};
+use poise::serenity_prelude::{self as serenity, CreateEmbed};
+use poise::{ChoiceParameter as _, CreateReply};
+
+/// Format setting value as a mention where possible
+fn mention(key: Key, id: u64) -> String {
+    match key.kind() {
+        Kind::Channel => format!("<#{id}>"),
+        Kind::Role => format!("<@&{id}>"),
+        Kind::Server => format!("`{id}`"),
+    }
+}
+
+/// Unreachable, used to create `config` command folder
+#[allow(clippy::unused_async)]
+#[poise::command(
+    slash_command,
+    check = "committee",
+    subcommands("show_config", "set_config", "reset_config")
+)]
+pub(crate) async fn config(_ctx: ACtx<'_>) -> Result<(), Error> {
+    unreachable!()
+}
+
+/// Show the roles and channels Nano uses, and which are set over the environment defaults
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "show")]
+pub(crate) async fn show_config(ctx: ACtx<'_>) -> Result<(), Error> {
+    tracing::info!("{}", ctx.author().name);
+    let guild = ctx.data().guild();
+    let overridden = ctx.data().config.overridden().await?;
+    let embed =
+        Key::ALL
+            .into_iter()
+            .fold(CreateEmbed::new().title("Configuration"), |embed, key| {
+                let source = if overridden.contains(&key) {
+                    "set"
+                } else {
+                    "default"
+                };
+                embed.field(
+                    key.name(),
+                    format!("{} ({source})", mention(key, guild.get(key))),
+                    true,
+                )
+            });
+    ctx.send(CreateReply::default().embed(embed)).await?;
+    Ok(())
+}
+
+/// Reply to a setting change, running diagnostics against the new server if it changed
+///
+/// Roles and channels belong to a server, so the report lists the settings that still
+/// need changing for it
+async fn reply_with_diagnostics(ctx: ACtx<'_>, setting: Key, content: String) -> Result<(), Error> {
+    if setting != Key::Server {
+        ctx.say(content).await?;
+        return Ok(());
+    }
+    let report = Report::run(ctx.http(), ctx.data()).await;
+    report.log();
+    let mut content =
+        format!("{content}. Commands registered by server need registering in it with `~cmds`");
+    if report.fatal() {
+        content.push_str(", and verification is broken until the failing settings are set");
+    }
+    ctx.send(
+        CreateReply::default()
+            .content(content)
+            .embed(report.embed()),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Set a role or channel, checking it exists in the server
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "set")]
+pub(crate) async fn set_config(
+    ctx: ACtx<'_>,
+    #[description = "Setting to change"] setting: Key,
+    #[description = "Role or channel mention, or ID"] value: String,
+) -> Result<(), Error> {
+    tracing::info!("{} {} {value}", ctx.author().name, setting.key());
+    let Some(id) = value
+        .trim()
+        .trim_start_matches(['<', '@', '&', '#'])
+        .trim_end_matches('>')
+        .parse::<u64>()
+        .ok()
+        .filter(|&id| id != 0)
+    else {
+        ctx.say(format!("Invalid ID: {value}")).await?;
+        return Ok(());
+    };
+
+    let server = ctx.data().guild().server;
+    let exists = match setting.kind() {
+        Kind::Channel => server
+            .channels(ctx.http())
+            .await?
+            .contains_key(&serenity::ChannelId::new(id)),
+        Kind::Role => server
+            .roles(ctx.http())
+            .await?
+            .contains_key(&serenity::RoleId::new(id)),
+        Kind::Server => serenity::GuildId::new(id)
+            .to_partial_guild(ctx.http())
+            .await
+            .is_ok(),
+    };
+    if !exists {
+        ctx.say(format!(
+            "{} {} not found in the server",
+            setting.name(),
+            mention(setting, id)
+        ))
+        .await?;
+        return Ok(());
+    }
+
+    ctx.defer().await?;
+    ctx.data().config.set(ctx.into(), setting, id).await?;
+    let content = format!("Set {} to {}", setting.name(), mention(setting, id));
+    reply_with_diagnostics(ctx, setting, content).await
+}
+
+/// Return a setting to its environment default
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "reset")]
+pub(crate) async fn reset_config(
+    ctx: ACtx<'_>,
+    #[description = "Setting to reset"] setting: Key,
+) -> Result<(), Error> {
+    tracing::info!("{} {}", ctx.author().name, setting.key());
+    ctx.defer().await?;
+    if ctx.data().config.reset(ctx.into(), setting).await? {
+        let id = ctx.data().guild().get(setting);
+        let content = format!(
+            "Reset {} to default {}",
+            setting.name(),
+            mention(setting, id)
+        );
+        reply_with_diagnostics(ctx, setting, content).await?;
+    } else {
+        ctx.say(format!("{} is already the default", setting.name()))
+            .await?;
+    }
+    Ok(())
+}
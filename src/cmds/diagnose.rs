@@ -0,0 +1,15 @@
+use crate::{cmds::checks::committee, diagnose::Report, ACtx, Error};
+use poise::CreateReply;
+
+/// Check the configured roles and channels, EA API and fuzzy extension
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, check = "committee")]
+pub(crate) async fn diagnose(ctx: ACtx<'_>) -> Result<(), Error> {
+    tracing::info!("{}", ctx.author().name);
+    ctx.defer().await?;
+    let report = Report::run(ctx.http(), ctx.data()).await;
+    report.log();
+    ctx.send(CreateReply::default().embed(report.embed()))
+        .await?;
+    Ok(())
+}
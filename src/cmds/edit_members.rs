@@ -1,10 +1,11 @@
-use crate::{db, verify, ACtx, Error, Fresher};
+use crate::{cmds::checks::committee, db, verify, ACtx, Error, Fresher};
 use poise::serenity_prelude as serenity;
 
 /// Unreachable, used to create `edit_member` command folder
 #[allow(clippy::unused_async)]
 #[poise::command(
     slash_command,
+    check = "committee",
     subcommands(
         "edit_member_shortcode",
         "edit_member_nickname",
@@ -25,7 +26,7 @@ pub(crate) async fn edit_member_shortcode(
     shortcode: String,
 ) -> Result<(), Error> {
     tracing::info!("{} {shortcode}", ctx.author().name);
-    if db::edit_member_shortcode(&ctx.data().db, id.user.id.into(), &shortcode).await? {
+    if db::edit_member_shortcode(&ctx.data().db, ctx.into(), id.user.id.into(), &shortcode).await? {
         ctx.say(format!("{id} Shortcode updated to {shortcode}"))
             .await?;
     } else {
@@ -44,7 +45,7 @@ pub(crate) async fn edit_member_nickname(
     nickname: String,
 ) -> Result<(), Error> {
     tracing::info!("{} {nickname}", ctx.author().name);
-    if db::edit_member_nickname(&ctx.data().db, id.user.id.into(), &nickname).await? {
+    if db::edit_member_nickname(&ctx.data().db, ctx.into(), id.user.id.into(), &nickname).await? {
         ctx.say(format!("{id} Nick updated to {nickname}")).await?;
     } else {
         ctx.say(format!("Failed to update Nick for {id}")).await?;
@@ -61,7 +62,7 @@ pub(crate) async fn edit_member_realname(
     realname: String,
 ) -> Result<(), Error> {
     tracing::info!("{} {realname}", ctx.author().name);
-    if db::edit_member_realname(&ctx.data().db, id.user.id.into(), &realname).await? {
+    if db::edit_member_realname(&ctx.data().db, ctx.into(), id.user.id.into(), &realname).await? {
         ctx.say(format!("{id} Name updated to {realname}")).await?;
     } else {
         ctx.say(format!("Failed to update Name for {id}")).await?;
@@ -78,20 +79,21 @@ pub(crate) async fn edit_member_fresher(
     fresher: Fresher,
 ) -> Result<(), Error> {
     tracing::info!("{} {} {fresher}", ctx.author().name, id.user.name);
-    if db::edit_member_fresher(&ctx.data().db, id.user.id.into(), fresher).await? {
+    if db::edit_member_fresher(&ctx.data().db, ctx.into(), id.user.id.into(), fresher).await? {
         let context = ctx.serenity_context();
+        let guild = ctx.data().guild();
         match fresher {
             Fresher::No => {
-                verify::remove_role(context, &mut id, ctx.data().fresher_pg).await?;
-                verify::remove_role(context, &mut id, ctx.data().fresher_ug).await?;
+                verify::remove_role(context, &mut id, guild.fresher_pg).await?;
+                verify::remove_role(context, &mut id, guild.fresher_ug).await?;
             }
             Fresher::YesPg => {
-                verify::apply_role(context, &mut id, ctx.data().fresher_pg).await?;
-                verify::remove_role(context, &mut id, ctx.data().fresher_ug).await?;
+                verify::apply_role(context, &mut id, guild.fresher_pg).await?;
+                verify::remove_role(context, &mut id, guild.fresher_ug).await?;
             }
             Fresher::YesUg => {
-                verify::remove_role(context, &mut id, ctx.data().fresher_pg).await?;
-                verify::apply_role(context, &mut id, ctx.data().fresher_ug).await?;
+                verify::remove_role(context, &mut id, guild.fresher_pg).await?;
+                verify::apply_role(context, &mut id, guild.fresher_ug).await?;
             }
         }
         ctx.say(format!("{id} Fresher status updated to {fresher}"))
@@ -105,44 +107,43 @@ pub(crate) async fn edit_member_fresher(
 
 /// Set all members with no roles to non-member
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn refresh_non_members(ctx: ACtx<'_>) -> Result<(), Error> {
     use serenity::futures::StreamExt;
     tracing::info!("{}", ctx.author().name);
     ctx.defer().await?;
-    let mut members = ctx.data().server.members_iter(ctx.http()).boxed();
+    let guild = ctx.data().guild();
+    let mut members = guild.server.members_iter(ctx.http()).boxed();
     let mut cnt = 0;
     while let Some(Ok(m)) = members.next().await {
         if m.roles.is_empty() {
-            m.add_role(ctx.http(), ctx.data().non_member).await?;
+            m.add_role(ctx.http(), guild.non_member).await?;
             cnt += 1;
         }
     }
     tracing::info!("{cnt} users given non-member role");
-    ctx.say(format!(
-        "{cnt} users given <@&{}> role",
-        ctx.data().non_member
-    ))
-    .await?;
+    ctx.say(format!("{cnt} users given <@&{}> role", guild.non_member))
+        .await?;
     Ok(())
 }
 
 /// Set all members to non-freshers
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn set_members_non_fresher(ctx: ACtx<'_>) -> Result<(), Error> {
     use serenity::futures::StreamExt;
     tracing::info!("{}", ctx.author().name);
-    let updated = db::set_members_non_fresher(&ctx.data().db).await?;
+    let updated = db::set_members_non_fresher(&ctx.data().db, ctx.into()).await?;
+    let guild = ctx.data().guild();
     ctx.say(format!("{updated} updated to non-fresher, removing roles"))
         .await?;
-    let mut members = ctx.data().server.members_iter(ctx.http()).boxed();
+    let mut members = guild.server.members_iter(ctx.http()).boxed();
     while let Some(Ok(m)) = members.next().await {
-        if m.roles.contains(&ctx.data().fresher_pg) {
-            let _ = m.remove_role(ctx.http(), ctx.data().fresher_pg).await;
+        if m.roles.contains(&guild.fresher_pg) {
+            let _ = m.remove_role(ctx.http(), guild.fresher_pg).await;
         }
-        if m.roles.contains(&ctx.data().fresher_ug) {
-            let _ = m.remove_role(ctx.http(), ctx.data().fresher_ug).await;
+        if m.roles.contains(&guild.fresher_ug) {
+            let _ = m.remove_role(ctx.http(), guild.fresher_ug).await;
         }
     }
     ctx.say("Roles removed").await?;
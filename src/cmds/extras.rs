@@ -1,4 +1,7 @@
-use crate::{db, verify, ACtx, Error, Gaijin};
+use crate::{
+    cmds::checks::{committee, reviewer},
+    db, verify, ACtx, Error, Gaijin,
+};
 use poise::{
     serenity_prelude::{self as serenity, CreateAttachment, CreateMessage},
     Modal,
@@ -6,7 +9,7 @@ use poise::{
 
 /// Get the number of entries in the gaijin table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "reviewer")]
 pub(crate) async fn count_gaijin(ctx: ACtx<'_>) -> Result<(), Error> {
     tracing::info!("{}", ctx.author().name);
     let count = db::count_gaijin(&ctx.data().db).await?;
@@ -17,16 +20,17 @@ pub(crate) async fn count_gaijin(ctx: ACtx<'_>) -> Result<(), Error> {
 
 /// Delete gaijin info by Discord ID
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn delete_gaijin(
     ctx: ACtx<'_>,
     mut id: serenity::Member,
     remove_roles: Option<bool>,
 ) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
-    if db::delete_gaijin_by_id(&ctx.data().db, id.user.id.into()).await? {
+    if db::delete_gaijin_by_id(&ctx.data().db, ctx.into(), id.user.id.into()).await? {
         if remove_roles.unwrap_or(true) {
-            verify::remove_role(ctx.serenity_context(), &mut id, ctx.data().gaijin).await?;
+            let guild = ctx.data().guild();
+            verify::remove_role(ctx.serenity_context(), &mut id, guild.gaijin).await?;
         }
         ctx.say(format!("Successfully deleted gaijin info for {id}"))
             .await?
@@ -39,7 +43,7 @@ pub(crate) async fn delete_gaijin(
 
 /// Print all gaijin in gaijin table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn get_all_gaijin(ctx: ACtx<'_>) -> Result<(), Error> {
     #[derive(Modal)]
     struct Confirm {
@@ -73,7 +77,11 @@ pub(crate) async fn get_all_gaijin(ctx: ACtx<'_>) -> Result<(), Error> {
 
 /// Unreachable, used to create `get_gaijin` command folder
 #[allow(clippy::unused_async)]
-#[poise::command(slash_command, subcommands("get_gaijin_by_id", "get_gaijin_by_name"))]
+#[poise::command(
+    slash_command,
+    check = "reviewer",
+    subcommands("get_gaijin_by_id", "get_gaijin_by_name")
+)]
 pub(crate) async fn get_gaijin(_ctx: ACtx<'_>) -> Result<(), Error> {
     unreachable!()
 }
@@ -111,7 +119,7 @@ pub(crate) async fn get_gaijin_by_name(ctx: ACtx<'_>, name: String) -> Result<()
 
 /// Add a gaijin to the gaijin table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn add_gaijin(
     ctx: ACtx<'_>,
     mut id: serenity::Member,
@@ -125,6 +133,7 @@ pub(crate) async fn add_gaijin(
     );
     db::insert_gaijin(
         &ctx.data().db,
+        ctx.into(),
         Gaijin {
             discord_id: id.user.id.into(),
             name,
@@ -132,8 +141,10 @@ pub(crate) async fn add_gaijin(
         },
     )
     .await?;
-    verify::remove_role(ctx.serenity_context(), &mut id, ctx.data().non_member).await?;
-    verify::apply_role(ctx.serenity_context(), &mut id, ctx.data().gaijin).await?;
+    ctx.data().webhooks.wake();
+    let guild = ctx.data().guild();
+    verify::remove_role(ctx.serenity_context(), &mut id, guild.non_member).await?;
+    verify::apply_role(ctx.serenity_context(), &mut id, guild.gaijin).await?;
     ctx.say(format!("Gaijin added: {id}")).await?;
     Ok(())
 }
@@ -142,6 +153,7 @@ pub(crate) async fn add_gaijin(
 #[allow(clippy::unused_async)]
 #[poise::command(
     slash_command,
+    check = "committee",
     subcommands("edit_gaijin_name", "edit_gaijin_university")
 )]
 pub(crate) async fn edit_gaijin(_ctx: ACtx<'_>) -> Result<(), Error> {
@@ -157,7 +169,7 @@ pub(crate) async fn edit_gaijin_name(
     name: String,
 ) -> Result<(), Error> {
     tracing::info!("{} {name}", ctx.author().name);
-    if db::edit_gaijin_name(&ctx.data().db, id.user.id.into(), &name).await? {
+    if db::edit_gaijin_name(&ctx.data().db, ctx.into(), id.user.id.into(), &name).await? {
         ctx.say(format!("{id} Name updated to {name}")).await?;
     } else {
         ctx.say(format!("Failed to update Name for {id}")).await?;
@@ -174,7 +186,9 @@ pub(crate) async fn edit_gaijin_university(
     university: String,
 ) -> Result<(), Error> {
     tracing::info!("{} {university}", ctx.author().name);
-    if db::edit_gaijin_university(&ctx.data().db, id.user.id.into(), &university).await? {
+    if db::edit_gaijin_university(&ctx.data().db, ctx.into(), id.user.id.into(), &university)
+        .await?
+    {
         ctx.say(format!("{id} University updated to {university}"))
             .await?;
     } else {
@@ -0,0 +1,92 @@
+use crate::{auth, cmds::checks::committee, db, ACtx, Error};
+use poise::CreateReply;
+
+/// Unreachable, used to create `api_key` command folder
+#[allow(clippy::unused_async)]
+#[poise::command(
+    slash_command,
+    check = "committee",
+    subcommands("create_api_key", "list_api_keys", "revoke_api_key")
+)]
+pub(crate) async fn api_key(_ctx: ACtx<'_>) -> Result<(), Error> {
+    unreachable!()
+}
+
+/// Create HTTP API key, the key is only shown once
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "create")]
+pub(crate) async fn create_api_key(
+    ctx: ACtx<'_>,
+    #[description = "Name of the client using the key"] name: String,
+    #[description = "Comma-separated scopes: export, import, verify, lookup, review"]
+    scopes: String,
+) -> Result<(), Error> {
+    tracing::info!("{} {name} {scopes}", ctx.author().name);
+    let scopes = match scopes
+        .split(',')
+        .map(str::parse::<auth::Scope>)
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(scopes) => scopes
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        Err(e) => {
+            ctx.say(e).await?;
+            return Ok(());
+        }
+    };
+    let key = auth::generate_key();
+    let hash = auth::hash_key(&key);
+    let content = match db::insert_api_key(&ctx.data().db, ctx.into(), &name, &hash, &scopes).await
+    {
+        Ok(()) => format!(
+            "Created API key `{name}` with scopes `{scopes}`, this will not be shown again:\n\
+            ```\n{key}\n```\nSend as `Authorization: Bearer <key>`, or sign requests with \
+            the HMAC secret `{}`",
+            ctx.data().auth.signing_secret(&hash)
+        ),
+        Err(e) => format!("Error: {e}"),
+    };
+    ctx.send(CreateReply::default().ephemeral(true).content(content))
+        .await?;
+    Ok(())
+}
+
+/// List HTTP API keys
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "list")]
+pub(crate) async fn list_api_keys(ctx: ACtx<'_>) -> Result<(), Error> {
+    tracing::info!("{}", ctx.author().name);
+    let keys = db::get_all_api_keys(&ctx.data().db).await?;
+    if keys.is_empty() {
+        ctx.say("No API keys found").await?;
+    } else {
+        let list = keys
+            .iter()
+            .map(|k| {
+                format!(
+                    "- `{}` ({}) created <t:{}:d>",
+                    k.name, k.scopes, k.created_at
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        ctx.say(list).await?;
+    }
+    Ok(())
+}
+
+/// Revoke HTTP API key by name
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "revoke")]
+pub(crate) async fn revoke_api_key(ctx: ACtx<'_>, name: String) -> Result<(), Error> {
+    tracing::info!("{} {name}", ctx.author().name);
+    if db::delete_api_key(&ctx.data().db, ctx.into(), &name).await? {
+        ctx.say(format!("Revoked API key `{name}`")).await?;
+    } else {
+        ctx.say(format!("No API key found named `{name}`")).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::{cmds::checks::committee, db, ACtx, Error, LookupEntry};
+use poise::serenity_prelude as serenity;
+
+const LOOKUP_LOG_LIMIT: i64 = 100;
+const LOOKUP_LOG_PAGE_SIZE: usize = 10;
+
+/// Format lookup log entry for display
+fn format_entry(e: &LookupEntry) -> String {
+    let result = if e.verified { "member" } else { "not member" };
+    format!(
+        "`#{}` <t:{}:f> **{}** looked up <@{}> ({result})\n",
+        e.id, e.created_at, e.client, e.discord_id
+    )
+}
+
+/// Search the log of membership lookups made through the HTTP API
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, check = "committee")]
+pub(crate) async fn lookup_log(
+    ctx: ACtx<'_>,
+    #[description = "Name of the API key used"] client: Option<String>,
+    #[description = "User who was looked up"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    tracing::info!("{}", ctx.author().name);
+    let entries = db::get_lookup_log(
+        &ctx.data().db,
+        client,
+        user.map(|u| u.id.into()),
+        LOOKUP_LOG_LIMIT,
+    )
+    .await?;
+    if entries.is_empty() {
+        ctx.say("No lookup log entries found").await?;
+        return Ok(());
+    }
+    let pages = entries
+        .chunks(LOOKUP_LOG_PAGE_SIZE)
+        .map(|page| page.iter().map(format_entry).collect::<String>())
+        .collect::<Vec<_>>();
+    let pages = pages.iter().map(String::as_str).collect::<Vec<_>>();
+    poise::builtins::paginate(ctx.into(), &pages).await?;
+    Ok(())
+}
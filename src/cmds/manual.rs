@@ -1,10 +1,13 @@
-use crate::{db, ACtx, Error, Fresher, ManualMember};
+use crate::{
+    cmds::checks::{committee, reviewer},
+    db, ACtx, Error, Fresher, ManualMember,
+};
 use poise::serenity_prelude::{self as serenity, CreateAttachment, CreateMessage};
 use poise::Modal;
 
 /// Get the number of manual members in the manual table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "reviewer")]
 pub(crate) async fn count_manual(ctx: ACtx<'_>) -> Result<(), Error> {
     tracing::info!("{}", ctx.author().name);
     let count = db::count_manual(&ctx.data().db).await?;
@@ -15,10 +18,10 @@ pub(crate) async fn count_manual(ctx: ACtx<'_>) -> Result<(), Error> {
 
 /// Delete manual member info by Discord ID
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn delete_manual(ctx: ACtx<'_>, id: serenity::Member) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
-    if db::delete_manual_by_id(&ctx.data().db, id.user.id.into()).await? {
+    if db::delete_manual_by_id(&ctx.data().db, ctx.into(), id.user.id.into()).await? {
         ctx.say(format!("Successfully deleted manual member info for {id}"))
             .await?
     } else {
@@ -30,7 +33,7 @@ pub(crate) async fn delete_manual(ctx: ACtx<'_>, id: serenity::Member) -> Result
 
 /// Print all manual members in manual table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn get_all_manual(ctx: ACtx<'_>) -> Result<(), Error> {
     #[derive(Modal)]
     struct ConfirmManual {
@@ -64,7 +67,7 @@ pub(crate) async fn get_all_manual(ctx: ACtx<'_>) -> Result<(), Error> {
 
 /// Get manual member info by Discord ID
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "reviewer")]
 pub(crate) async fn get_manual(ctx: ACtx<'_>, id: serenity::Member) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
     match db::get_manual_by_id(&ctx.data().db, id.user.id.into()).await? {
@@ -79,7 +82,7 @@ pub(crate) async fn get_manual(ctx: ACtx<'_>, id: serenity::Member) -> Result<()
 
 /// Manually add manual member to manual table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn add_manual(
     ctx: ACtx<'_>,
     id: serenity::Member,
@@ -95,6 +98,7 @@ pub(crate) async fn add_manual(
     );
     db::insert_manual(
         &ctx.data().db,
+        ctx.into(),
         ManualMember {
             discord_id: id.user.id.into(),
             shortcode,
@@ -110,7 +114,7 @@ pub(crate) async fn add_manual(
 
 /// Delete all manual members in manual table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn delete_all_manual(ctx: ACtx<'_>) -> Result<(), Error> {
     #[derive(Modal)]
     struct ConfirmPurgeManual {
@@ -123,7 +127,7 @@ pub(crate) async fn delete_all_manual(ctx: ACtx<'_>) -> Result<(), Error> {
 
     if let Some(ConfirmPurgeManual { confirm }) = ConfirmPurgeManual::execute(ctx).await? {
         if confirm.to_lowercase().contains("yes") {
-            let deleted = db::delete_all_manual(&ctx.data().db).await?;
+            let deleted = db::delete_all_manual(&ctx.data().db, ctx.into()).await?;
             ctx.say(format!("Deleted {deleted} entries from the manual db"))
                 .await?;
             Ok(())
@@ -1,10 +1,14 @@
-use crate::{db, verify, ACtx, Error, Fresher, Member};
-use poise::serenity_prelude::{self as serenity, CreateAttachment, CreateMessage};
+use crate::{
+    academic_year,
+    cmds::checks::{committee, reviewer},
+    db, metrics, verify, ACtx, Error, Fresher, Member, Method,
+};
+use poise::serenity_prelude::{self as serenity, CreateAttachment, CreateEmbed, CreateMessage};
 use poise::Modal;
 
 /// Get the number of members in the members table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "reviewer")]
 pub(crate) async fn count_members(ctx: ACtx<'_>) -> Result<(), Error> {
     tracing::info!("{}", ctx.author().name);
     let count = db::count_members(&ctx.data().db).await?;
@@ -15,18 +19,20 @@ pub(crate) async fn count_members(ctx: ACtx<'_>) -> Result<(), Error> {
 
 /// Delete member info by Discord ID
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn delete_member(
     ctx: ACtx<'_>,
     mut id: serenity::Member,
     remove_roles: Option<bool>,
 ) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
-    if db::delete_member_by_id(&ctx.data().db, id.user.id.into()).await? {
+    if db::delete_member_by_id(&ctx.data().db, ctx.into(), id.user.id.into()).await? {
+        ctx.data().webhooks.wake();
         if remove_roles.unwrap_or(true) {
-            verify::remove_role(ctx.serenity_context(), &mut id, ctx.data().member).await?;
-            verify::remove_role(ctx.serenity_context(), &mut id, ctx.data().fresher_pg).await?;
-            verify::remove_role(ctx.serenity_context(), &mut id, ctx.data().fresher_ug).await?;
+            let guild = ctx.data().guild();
+            verify::remove_role(ctx.serenity_context(), &mut id, guild.member).await?;
+            verify::remove_role(ctx.serenity_context(), &mut id, guild.fresher_pg).await?;
+            verify::remove_role(ctx.serenity_context(), &mut id, guild.fresher_ug).await?;
         }
         ctx.say(format!("Successfully deleted member info for {id}"))
             .await?
@@ -39,7 +45,7 @@ pub(crate) async fn delete_member(
 
 /// Print all members in members table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn get_all_members(ctx: ACtx<'_>) -> Result<(), Error> {
     #[derive(Modal)]
     struct Confirm {
@@ -75,6 +81,7 @@ pub(crate) async fn get_all_members(ctx: ACtx<'_>) -> Result<(), Error> {
 #[allow(clippy::unused_async)]
 #[poise::command(
     slash_command,
+    check = "reviewer",
     subcommands(
         "get_member_by_id",
         "get_member_by_shortcode",
@@ -93,8 +100,16 @@ pub(crate) async fn get_member_by_id(ctx: ACtx<'_>, id: serenity::Member) -> Res
     tracing::info!("{} {}", ctx.author().name, id.user.name);
     match db::get_member_by_id(&ctx.data().db, id.user.id.into()).await? {
         Some(m) => {
-            ctx.say(format!("Member info for {id}:\n```rust\n{m:#?}\n```"))
+            let years = db::get_membership_years_by_id(&ctx.data().db, m.discord_id)
                 .await?
+                .iter()
+                .map(|y| academic_year(y.year))
+                .collect::<Vec<_>>()
+                .join(", ");
+            ctx.say(format!(
+                "Member info for {id}:\n```rust\n{m:#?}\n```\nMember for: {years}"
+            ))
+            .await?
         }
         None => ctx.say(format!("No member entry found for {id}")).await?,
     };
@@ -163,7 +178,7 @@ pub(crate) async fn get_member_by_realname(ctx: ACtx<'_>, realname: String) -> R
 
 /// Add a member to the members table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn add_member(
     ctx: ACtx<'_>,
     mut id: serenity::Member,
@@ -177,33 +192,45 @@ pub(crate) async fn add_member(
         ctx.author().name,
         id.user.name,
     );
-    db::insert_member(
-        &ctx.data().db,
-        Member {
-            discord_id: id.user.id.into(),
-            shortcode,
-            nickname,
-            realname,
-            fresher,
-        },
-    )
-    .await?;
+    let member = Member {
+        discord_id: id.user.id.into(),
+        shortcode,
+        nickname: nickname.clone(),
+        realname: realname.clone(),
+        fresher,
+        verified_at: Some(serenity::Timestamp::now().unix_timestamp()),
+        method: Method::Admin,
+        verified_by: Some(ctx.author().id.into()),
+    };
+    db::insert_member(&ctx.data().db, ctx.into(), member.clone()).await?;
 
     let context = ctx.serenity_context();
-    verify::remove_role(context, &mut id, ctx.data().non_member).await?;
-    verify::apply_role(context, &mut id, ctx.data().member).await?;
-    match fresher {
-        Fresher::No => {}
-        Fresher::YesPg => verify::apply_role(context, &mut id, ctx.data().fresher_pg).await?,
-        Fresher::YesUg => verify::apply_role(context, &mut id, ctx.data().fresher_ug).await?,
+    let guild = ctx.data().guild();
+    verify::remove_role(context, &mut id, guild.non_member).await?;
+    verify::apply_role(context, &mut id, guild.member).await?;
+    if let Some(role) = guild.fresher_role(fresher) {
+        verify::apply_role(context, &mut id, role).await?;
     }
+    metrics::verified(Method::Admin, fresher);
+    ctx.data().webhooks.wake();
     ctx.say(format!("Member added: {id}")).await?;
+    let embed = CreateEmbed::new()
+        .thumbnail(id.user.face())
+        .title("Member verified via admin")
+        .description(id.user.to_string())
+        .field("Fresher", fresher.to_string(), true)
+        .field("Nickname", nickname, true)
+        .field("Name", realname, true)
+        .field("Verified by", ctx.author().to_string(), true)
+        .timestamp(serenity::Timestamp::now());
+    let msg = CreateMessage::new().embed(verify::verification_fields(embed, &member));
+    guild.au_ch_id.send_message(ctx.http(), msg).await?;
     Ok(())
 }
 
 /// Manually add member to members table from pending table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn insert_member_from_pending(
     ctx: ACtx<'_>,
     id: serenity::Member,
@@ -211,10 +238,20 @@ pub(crate) async fn insert_member_from_pending(
     fresher: Fresher,
 ) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
-    match db::insert_member_from_pending(&ctx.data().db, id.user.id.into(), &nickname, fresher)
-        .await
+    match db::insert_member_from_pending(
+        &ctx.data().db,
+        ctx.into(),
+        id.user.id.into(),
+        &nickname,
+        fresher,
+        Method::Admin,
+        Some(ctx.author().id.into()),
+    )
+    .await
     {
         Ok(_) => {
+            metrics::verified(Method::Admin, fresher);
+            ctx.data().webhooks.wake();
             ctx.say(format!("Member moved from pending to members table: {id}"))
                 .await?
         }
@@ -225,14 +262,19 @@ pub(crate) async fn insert_member_from_pending(
 
 /// Manually add member to members table from manual table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn insert_member_from_manual(
     ctx: ACtx<'_>,
     id: serenity::Member,
 ) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
-    match db::insert_member_from_manual(&ctx.data().db, id.user.id.into()).await {
-        Ok(_) => {
+    let verified_by = Some(ctx.author().id.into());
+    match db::insert_member_from_manual(&ctx.data().db, ctx.into(), id.user.id.into(), verified_by)
+        .await
+    {
+        Ok(m) => {
+            metrics::verified(Method::Manual, m.fresher);
+            ctx.data().webhooks.wake();
             ctx.say(format!("Member moved from manual to members table: {id}"))
                 .await?
         }
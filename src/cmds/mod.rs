@@ -1,7 +1,10 @@
 use crate::{ACtx, Data, Error};
+use checks::committee;
 use poise::serenity_prelude::{self as serenity, CreateActionRow, CreateButton, CreateMessage};
 use poise::Modal;
 
+pub(crate) mod checks;
+
 pub(crate) mod members;
 pub(crate) use members::*;
 
@@ -20,6 +23,33 @@ pub(crate) use whois::*;
 pub(crate) mod extras;
 pub(crate) use extras::*;
 
+pub(crate) mod audit;
+pub(crate) use audit::*;
+
+pub(crate) mod years;
+pub(crate) use years::*;
+
+pub(crate) mod attempts;
+pub(crate) use attempts::*;
+
+pub(crate) mod keys;
+pub(crate) use keys::*;
+
+pub(crate) mod lookups;
+pub(crate) use lookups::*;
+
+pub(crate) mod webhooks;
+pub(crate) use webhooks::*;
+
+pub(crate) mod config;
+pub(crate) use config::*;
+
+pub(crate) mod diagnose;
+pub(crate) use diagnose::*;
+
+pub(crate) mod templates;
+pub(crate) use templates::*;
+
 /// Buttons to (de-)register application commands globally or by guild
 #[tracing::instrument(skip_all)]
 #[poise::command(prefix_command, owners_only)]
@@ -31,7 +61,7 @@ pub(crate) async fn cmds(ctx: poise::Context<'_, Data, Error>) -> Result<(), Err
 
 /// Send (customisable) verification introduction message in specified channel
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn setup(
     ctx: ACtx<'_>,
     #[description = "Channel to send verification introduction message in"]
@@ -123,5 +153,15 @@ pub(crate) fn all_commands() -> Vec<poise::Command<Data, Error>> {
         get_gaijin(),
         add_gaijin(),
         edit_gaijin(),
+        audit(),
+        renew_member(),
+        rollover(),
+        reset_attempts(),
+        api_key(),
+        lookup_log(),
+        webhook(),
+        config(),
+        diagnose(),
+        template(),
     ]
 }
@@ -1,10 +1,13 @@
-use crate::{db, ACtx, Error, PendingMember};
+use crate::{
+    cmds::checks::{committee, reviewer},
+    db, ACtx, Error, PendingMember,
+};
 use poise::serenity_prelude::{self as serenity, CreateAttachment, CreateMessage};
 use poise::Modal;
 
 /// Get the number of pending members in the pending table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "reviewer")]
 pub(crate) async fn count_pending(ctx: ACtx<'_>) -> Result<(), Error> {
     tracing::info!("{}", ctx.author().name);
     let count = db::count_pending(&ctx.data().db).await?;
@@ -15,10 +18,10 @@ pub(crate) async fn count_pending(ctx: ACtx<'_>) -> Result<(), Error> {
 
 /// Delete pending member info by Discord ID
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn delete_pending(ctx: ACtx<'_>, id: serenity::Member) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
-    if db::delete_pending_by_id(&ctx.data().db, id.user.id.into()).await? {
+    if db::delete_pending_by_id(&ctx.data().db, ctx.into(), id.user.id.into()).await? {
         ctx.say(format!("Successfully deleted pending member info for {id}"))
             .await?
     } else {
@@ -30,7 +33,7 @@ pub(crate) async fn delete_pending(ctx: ACtx<'_>, id: serenity::Member) -> Resul
 
 /// Print all pending members in pending table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn get_all_pending(ctx: ACtx<'_>) -> Result<(), Error> {
     #[derive(Modal)]
     struct ConfirmPending {
@@ -64,7 +67,7 @@ pub(crate) async fn get_all_pending(ctx: ACtx<'_>) -> Result<(), Error> {
 
 /// Get pending member info by Discord ID
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "reviewer")]
 pub(crate) async fn get_pending(ctx: ACtx<'_>, id: serenity::Member) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
     match db::get_pending_by_id(&ctx.data().db, id.user.id.into()).await? {
@@ -79,7 +82,7 @@ pub(crate) async fn get_pending(ctx: ACtx<'_>, id: serenity::Member) -> Result<(
 
 /// Manually add pending member to pending table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn add_pending(
     ctx: ACtx<'_>,
     id: serenity::Member,
@@ -93,6 +96,7 @@ pub(crate) async fn add_pending(
     );
     db::insert_pending(
         &ctx.data().db,
+        ctx.into(),
         PendingMember {
             discord_id: id.user.id.into(),
             shortcode,
@@ -106,7 +110,7 @@ pub(crate) async fn add_pending(
 
 /// Delete all pending members in pending table
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command)]
+#[poise::command(slash_command, check = "committee")]
 pub(crate) async fn delete_all_pending(ctx: ACtx<'_>) -> Result<(), Error> {
     #[derive(Modal)]
     struct ConfirmPurgePending {
@@ -119,7 +123,7 @@ pub(crate) async fn delete_all_pending(ctx: ACtx<'_>) -> Result<(), Error> {
 
     if let Some(ConfirmPurgePending { confirm }) = ConfirmPurgePending::execute(ctx).await? {
         if confirm.to_lowercase().contains("yes") {
-            let deleted = db::delete_all_pending(&ctx.data().db).await?;
+            let deleted = db::delete_all_pending(&ctx.data().db, ctx.into()).await?;
             ctx.say(format!("Deleted {deleted} entries from the pending db"))
                 .await?;
             Ok(())
@@ -0,0 +1,145 @@
+use crate::{cmds::checks::committee, db, locale::Locale, templates::Template, ACtx, Error};
+use poise::{serenity_prelude::CreateEmbed, ChoiceParameter as _, CreateReply, Modal};
+
+#[derive(Modal)]
+#[name = "Edit template"]
+struct EditTemplate {
+    #[name = "Template text"]
+    #[paragraph]
+    #[max_length = 2000]
+    body: String,
+}
+
+/// Unreachable, used to create `template` command folder
+#[allow(clippy::unused_async)]
+#[poise::command(
+    slash_command,
+    check = "committee",
+    subcommands(
+        "list_templates",
+        "edit_template",
+        "preview_template",
+        "reset_template"
+    )
+)]
+pub(crate) async fn template(_ctx: ACtx<'_>) -> Result<(), Error> {
+    unreachable!()
+}
+
+/// List message templates, their placeholders and which locales have been edited
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "list")]
+pub(crate) async fn list_templates(ctx: ACtx<'_>) -> Result<(), Error> {
+    tracing::info!("{}", ctx.author().name);
+    let edited = db::get_all_templates(&ctx.data().db).await?;
+    let list = Template::ALL
+        .iter()
+        .map(|t| {
+            let placeholders = t
+                .placeholders()
+                .iter()
+                .map(|p| format!("`{{{p}}}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let status = Locale::ALL
+                .iter()
+                .filter_map(|&l| {
+                    let key = t.locale_key(l);
+                    let e = edited.iter().find(|e| e.key == key)?;
+                    Some(format!("{} edited <t:{}:R>", l.code(), e.updated_at))
+                })
+                .collect::<Vec<_>>();
+            let status = if status.is_empty() {
+                "default".to_string()
+            } else {
+                status.join(", ")
+            };
+            format!("- {} ({status}): {placeholders}", t.name())
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    ctx.say(list).await?;
+    Ok(())
+}
+
+/// Edit a message template, placeholders are checked before saving
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "edit")]
+pub(crate) async fn edit_template(
+    ctx: ACtx<'_>,
+    #[description = "Template to edit"] template: Template,
+    #[description = "Language to edit, defaults to English"] locale: Option<Locale>,
+) -> Result<(), Error> {
+    let locale = locale.unwrap_or_default();
+    let key = template.locale_key(locale);
+    tracing::info!("{} {key}", ctx.author().name);
+    let body = template.body(&ctx.data().db, locale).await?;
+    let Some(EditTemplate { body }) =
+        EditTemplate::execute_with_defaults(ctx, EditTemplate { body }).await?
+    else {
+        ctx.say("Modal timed out, try again...").await?;
+        return Ok(());
+    };
+    if let Err(e) = template.validate(&body) {
+        let reply = CreateReply::default()
+            .ephemeral(true)
+            .content(format!("Template not saved: {e}"))
+            .embed(CreateEmbed::new().title("Your text").description(body));
+        ctx.send(reply).await?;
+        return Ok(());
+    }
+    db::set_template(&ctx.data().db, ctx.into(), &key, &body).await?;
+    ctx.say(format!(
+        "Saved {} ({}) template, check it with `/template preview`",
+        template.name(),
+        locale.name()
+    ))
+    .await?;
+    Ok(())
+}
+
+/// Preview a message template as it would be sent to you
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "preview")]
+pub(crate) async fn preview_template(
+    ctx: ACtx<'_>,
+    #[description = "Template to preview"] template: Template,
+    #[description = "Language to preview, defaults to English"] locale: Option<Locale>,
+) -> Result<(), Error> {
+    let locale = locale.unwrap_or_default();
+    tracing::info!("{} {}", ctx.author().name, template.locale_key(locale));
+    let body = template.body(&ctx.data().db, locale).await?;
+    let preview = Template::fill(ctx.data(), &body, ctx.author(), "freshers");
+    ctx.send(CreateReply::default().ephemeral(true).content(preview))
+        .await?;
+    Ok(())
+}
+
+/// Return a message template to its default text
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "reset")]
+pub(crate) async fn reset_template(
+    ctx: ACtx<'_>,
+    #[description = "Template to reset"] template: Template,
+    #[description = "Language to reset, defaults to English"] locale: Option<Locale>,
+) -> Result<(), Error> {
+    let locale = locale.unwrap_or_default();
+    let key = template.locale_key(locale);
+    tracing::info!("{} {key}", ctx.author().name);
+    if db::delete_template(&ctx.data().db, ctx.into(), &key).await? {
+        ctx.say(format!(
+            "Reset {} ({}) template to default",
+            template.name(),
+            locale.name()
+        ))
+        .await?;
+    } else {
+        ctx.say(format!(
+            "{} ({}) template is already the default",
+            template.name(),
+            locale.name()
+        ))
+        .await?;
+    }
+    Ok(())
+}
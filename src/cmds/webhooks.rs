@@ -0,0 +1,64 @@
+use crate::{cmds::checks::committee, db, ACtx, Error, WebhookDelivery};
+
+const FAILED_LIMIT: i64 = 100;
+const FAILED_PAGE_SIZE: usize = 5;
+/// Characters of each delivery error shown, so a page stays within Discord's limit
+const ERROR_LENGTH: usize = 200;
+
+/// Unreachable, used to create `webhook` command folder
+#[allow(clippy::unused_async)]
+#[poise::command(
+    slash_command,
+    check = "committee",
+    subcommands("list_failed_webhooks", "replay_webhooks")
+)]
+pub(crate) async fn webhook(_ctx: ACtx<'_>) -> Result<(), Error> {
+    unreachable!()
+}
+
+/// Format failed delivery for display, truncating its error
+fn format_delivery(d: &WebhookDelivery) -> String {
+    let error = d.last_error.as_deref().unwrap_or("Unknown error");
+    let error = match error.char_indices().nth(ERROR_LENGTH) {
+        Some((end, _)) => format!("{}…", &error[..end]),
+        None => error.to_string(),
+    };
+    format!(
+        "- `{}` {} to <{}> created <t:{}:f>, {} attempts: {error}\n",
+        d.id, d.event, d.endpoint, d.created_at, d.attempts
+    )
+}
+
+/// List webhook deliveries that failed after all retries
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "failed")]
+pub(crate) async fn list_failed_webhooks(ctx: ACtx<'_>) -> Result<(), Error> {
+    tracing::info!("{}", ctx.author().name);
+    let failed = db::get_failed_webhook_deliveries(&ctx.data().db, FAILED_LIMIT).await?;
+    if failed.is_empty() {
+        ctx.say("No failed webhook deliveries").await?;
+        return Ok(());
+    }
+    let pages = failed
+        .chunks(FAILED_PAGE_SIZE)
+        .map(|page| page.iter().map(format_delivery).collect::<String>())
+        .collect::<Vec<_>>();
+    let pages = pages.iter().map(String::as_str).collect::<Vec<_>>();
+    poise::builtins::paginate(ctx.into(), &pages).await?;
+    Ok(())
+}
+
+/// Retry failed webhook deliveries, all of them if no ID is given
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, rename = "replay")]
+pub(crate) async fn replay_webhooks(
+    ctx: ACtx<'_>,
+    #[description = "ID of the failed delivery"] id: Option<i64>,
+) -> Result<(), Error> {
+    tracing::info!("{} {id:?}", ctx.author().name);
+    let replayed = db::replay_webhook_deliveries(&ctx.data().db, ctx.into(), id).await?;
+    ctx.data().webhooks.wake();
+    ctx.say(format!("Queued {replayed} webhook deliveries for replay"))
+        .await?;
+    Ok(())
+}
@@ -17,9 +17,15 @@ impl EphemeralReply for ACtx<'_> {
 }
 
 /// Update your nick according to nano (what shows up in `/whois`)
-#[poise::command(slash_command)]
+#[poise::command(
+    slash_command,
+    name_localized("zh-CN", "昵称"),
+    description_localized("zh-CN", "更新你在 Nano 中的昵称（显示在 `/whois` 中）")
+)]
 pub(crate) async fn nick(
     ctx: ACtx<'_>,
+    #[description = "New nickname"]
+    #[description_localized("zh-CN", "新昵称")]
     #[min_length = 2]
     #[max_length = 32]
     nickname: String,
@@ -29,7 +35,7 @@ pub(crate) async fn nick(
         .await?
         .map_or("<missing>".to_string(), |m| m.nickname);
     tracing::info!("{} {old_nickname} -> {nickname}", u.name);
-    if db::edit_member_nickname(&ctx.data().db, u.id.into(), &nickname).await? {
+    if db::edit_member_nickname(&ctx.data().db, ctx.into(), u.id.into(), &nickname).await? {
         ctx.ereply(format!("Nick updated to {nickname}")).await?;
         let embed = CreateEmbed::new()
             .title("Nick updated")
@@ -39,7 +45,11 @@ pub(crate) async fn nick(
             .field("New Nick", nickname, true)
             .timestamp(serenity::Timestamp::now());
         let msg = CreateMessage::new().embed(embed);
-        ctx.data().au_ch_id.send_message(ctx.http(), msg).await?;
+        ctx.data()
+            .guild()
+            .au_ch_id
+            .send_message(ctx.http(), msg)
+            .await?;
     } else {
         ctx.ereply("Failed to update nick, please try again or message committee for help")
             .await?;
@@ -51,6 +61,8 @@ pub(crate) async fn nick(
 #[allow(clippy::unused_async)]
 #[poise::command(
     slash_command,
+    name_localized("zh-CN", "查询"),
+    description_localized("zh-CN", "查询成员"),
     subcommands(
         "whois_by_id",
         "whois_by_nickname",
@@ -64,8 +76,17 @@ pub(crate) async fn whois(_ctx: ACtx<'_>) -> Result<(), Error> {
 
 /// (Public) Find member by Discord ID
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command, rename = "id")]
-pub(crate) async fn whois_by_id(ctx: ACtx<'_>, id: serenity::Member) -> Result<(), Error> {
+#[poise::command(
+    slash_command,
+    rename = "id",
+    description_localized("zh-CN", "（公开）按 Discord ID 查找成员")
+)]
+pub(crate) async fn whois_by_id(
+    ctx: ACtx<'_>,
+    #[description = "Discord member"]
+    #[description_localized("zh-CN", "Discord 成员")]
+    id: serenity::Member,
+) -> Result<(), Error> {
     tracing::info!("{} {}", ctx.author().name, id.user.name);
     match db::get_member_by_id(&ctx.data().db, id.user.id.into()).await? {
         Some(m) => ctx.ereply(format!("{id}: {}", m.nickname)).await?,
@@ -79,8 +100,18 @@ pub(crate) async fn whois_by_id(ctx: ACtx<'_>, id: serenity::Member) -> Result<(
 
 /// (Public) Find member by Nickname
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command, rename = "nick")]
-pub(crate) async fn whois_by_nickname(ctx: ACtx<'_>, nickname: String) -> Result<(), Error> {
+#[poise::command(
+    slash_command,
+    rename = "nick",
+    name_localized("zh-CN", "昵称"),
+    description_localized("zh-CN", "（公开）按昵称查找成员")
+)]
+pub(crate) async fn whois_by_nickname(
+    ctx: ACtx<'_>,
+    #[description = "Nickname to search for"]
+    #[description_localized("zh-CN", "要查找的昵称")]
+    nickname: String,
+) -> Result<(), Error> {
     tracing::info!("{} {nickname}", ctx.author().name);
     if let Some(m) = db::get_member_by_nickname(&ctx.data().db, &nickname).await? {
         ctx.ereply(format!("{nickname}: <@{}>", m.discord_id))
@@ -105,8 +136,18 @@ pub(crate) async fn whois_by_nickname(ctx: ACtx<'_>, nickname: String) -> Result
 
 /// (Public) Find member by Real Name
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command, rename = "name")]
-pub(crate) async fn whois_by_realname(ctx: ACtx<'_>, realname: String) -> Result<(), Error> {
+#[poise::command(
+    slash_command,
+    rename = "name",
+    name_localized("zh-CN", "姓名"),
+    description_localized("zh-CN", "（公开）按真实姓名查找成员")
+)]
+pub(crate) async fn whois_by_realname(
+    ctx: ACtx<'_>,
+    #[description = "Real name to search for"]
+    #[description_localized("zh-CN", "要查找的真实姓名")]
+    realname: String,
+) -> Result<(), Error> {
     tracing::info!("{} {realname}", ctx.author().name);
     if let Some(m) = db::get_member_by_realname(&ctx.data().db, &realname).await? {
         ctx.ereply(format!("{realname}: <@{}>", m.discord_id))
@@ -131,8 +172,18 @@ pub(crate) async fn whois_by_realname(ctx: ACtx<'_>, realname: String) -> Result
 
 /// (Public) Find gaijin by Name
 #[tracing::instrument(skip_all)]
-#[poise::command(slash_command, rename = "gaijin")]
-pub(crate) async fn whois_gaijin(ctx: ACtx<'_>, name: String) -> Result<(), Error> {
+#[poise::command(
+    slash_command,
+    rename = "gaijin",
+    name_localized("zh-CN", "校外"),
+    description_localized("zh-CN", "（公开）按姓名查找校外成员")
+)]
+pub(crate) async fn whois_gaijin(
+    ctx: ACtx<'_>,
+    #[description = "Name to search for"]
+    #[description_localized("zh-CN", "要查找的姓名")]
+    name: String,
+) -> Result<(), Error> {
     tracing::info!("{} {name}", ctx.author().name);
     if let Some(m) = db::get_gaijin_by_name(&ctx.data().db, &name).await? {
         ctx.ereply(format!("{name}: <@{}>", m.discord_id)).await?;
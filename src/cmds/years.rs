@@ -0,0 +1,112 @@
+use crate::{academic_year, cmds::checks::committee, db, mentions, verify, ACtx, Error};
+use poise::{
+    serenity_prelude::{self as serenity, CreateEmbed, CreateMessage},
+    CreateReply, Modal,
+};
+
+/// Renew member for the current academic year
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, check = "committee")]
+pub(crate) async fn renew_member(ctx: ACtx<'_>, mut id: serenity::Member) -> Result<(), Error> {
+    tracing::info!("{} {}", ctx.author().name, id.user.name);
+    if db::renew_member(&ctx.data().db, ctx.into(), id.user.id.into()).await? {
+        let context = ctx.serenity_context();
+        let guild = ctx.data().guild();
+        verify::apply_role(context, &mut id, guild.member).await?;
+        if id.roles.contains(&guild.old_member) {
+            verify::remove_role(context, &mut id, guild.old_member).await?;
+        }
+        let year = db::current_academic_year(&ctx.data().db).await?;
+        ctx.say(format!("{id} renewed for {}", academic_year(year)))
+            .await?;
+    } else {
+        ctx.say(format!("No member entry found for {id}")).await?;
+    }
+    Ok(())
+}
+
+/// Roll over to a new academic year, archiving freshers and demoting lapsed members
+#[tracing::instrument(skip_all)]
+#[poise::command(slash_command, check = "committee")]
+pub(crate) async fn rollover(
+    ctx: ACtx<'_>,
+    #[description = "Preview changes without applying them (default: true)"] dry_run: Option<bool>,
+) -> Result<(), Error> {
+    #[derive(Modal)]
+    struct ConfirmRollover {
+        #[name = "This will demote all lapsed members"]
+        #[placeholder = "yes"]
+        confirm: String,
+    }
+
+    tracing::info!("{}", ctx.author().name);
+
+    let data = ctx.data();
+    let year = db::current_academic_year(&data.db).await?;
+    let freshers = db::get_expired_freshers(&data.db, year).await?;
+    let lapsed = db::get_lapsed_members(&data.db, year).await?;
+    let embed = CreateEmbed::new()
+        .title(format!("Rollover to {}", academic_year(year)))
+        .field(
+            format!("Freshers to archive ({})", freshers.len()),
+            mentions(&freshers),
+            false,
+        )
+        .field(
+            format!("Lapsed members to move to old member ({})", lapsed.len()),
+            mentions(&lapsed),
+            false,
+        )
+        .timestamp(serenity::Timestamp::now());
+
+    if dry_run.unwrap_or(true) {
+        let reply = CreateReply::default().content("Rollover preview (dry run)");
+        ctx.send(reply.embed(embed)).await?;
+        return Ok(());
+    }
+
+    let Some(ConfirmRollover { confirm }) = ConfirmRollover::execute(ctx).await? else {
+        ctx.say("Timed out").await?;
+        return Ok(());
+    };
+    if !confirm.to_lowercase().contains("yes") {
+        ctx.say("Skipping rollover").await?;
+        return Ok(());
+    }
+    ctx.say("Rolling over, this may take a while...").await?;
+
+    let archived = db::archive_expired_freshers(&data.db, ctx.into(), year).await?;
+    let guild = data.guild();
+    for m in &archived {
+        let user = serenity::UserId::new(m.discord_id.cast_unsigned());
+        for role in [guild.fresher_pg, guild.fresher_ug] {
+            let _ = ctx
+                .http()
+                .remove_member_role(guild.server, user, role, None)
+                .await;
+        }
+    }
+    for m in &lapsed {
+        let user = serenity::UserId::new(m.discord_id.cast_unsigned());
+        let _ = ctx
+            .http()
+            .remove_member_role(guild.server, user, guild.member, None)
+            .await;
+        let _ = ctx
+            .http()
+            .add_member_role(guild.server, user, guild.old_member, None)
+            .await;
+    }
+    tracing::info!(
+        "Rollover to {}: {} freshers archived, {} members lapsed",
+        academic_year(year),
+        archived.len(),
+        lapsed.len()
+    );
+
+    let msg = CreateMessage::new().embed(embed.description(format!("By {}", ctx.author())));
+    guild.au_ch_id.send_message(ctx.http(), msg).await?;
+    ctx.say("Rollover complete, summary sent to added users channel")
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use crate::{db, var, Actor, Error, Fresher};
+use anyhow::Context as _;
+use poise::{serenity_prelude as serenity, ChoiceParameter};
+use std::sync::{Arc, RwLock};
+
+/// Kind of Discord object a setting refers to
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Kind {
+    Channel,
+    Role,
+    Server,
+}
+
+/// Guild setting that can be changed at runtime with `/config`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ChoiceParameter)]
+pub(crate) enum Key {
+    #[name = "Added users channel"]
+    AuChannel,
+    #[name = "General channel"]
+    GnChannel,
+    #[name = "Member role"]
+    Member,
+    #[name = "Non-member role"]
+    NonMember,
+    #[name = "Old member role"]
+    OldMember,
+    #[name = "Postgraduate fresher role"]
+    FresherPg,
+    #[name = "Undergraduate fresher role"]
+    FresherUg,
+    #[name = "Gaijin role"]
+    Gaijin,
+    #[name = "Server"]
+    Server,
+}
+
+impl Key {
+    pub(crate) const ALL: [Key; 9] = [
+        Key::AuChannel,
+        Key::GnChannel,
+        Key::Member,
+        Key::NonMember,
+        Key::OldMember,
+        Key::FresherPg,
+        Key::FresherUg,
+        Key::Gaijin,
+        Key::Server,
+    ];
+
+    /// Key in the settings table
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Key::AuChannel => "au_channel_id",
+            Key::GnChannel => "gn_channel_id",
+            Key::Member => "member_id",
+            Key::NonMember => "non_member_id",
+            Key::OldMember => "old_member_id",
+            Key::FresherPg => "fresher_pg_id",
+            Key::FresherUg => "fresher_ug_id",
+            Key::Gaijin => "gaijin_id",
+            Key::Server => "server_id",
+        }
+    }
+
+    pub(crate) fn kind(self) -> Kind {
+        match self {
+            Key::AuChannel | Key::GnChannel => Kind::Channel,
+            Key::Server => Kind::Server,
+            _ => Kind::Role,
+        }
+    }
+
+    pub(crate) fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.key() == key)
+    }
+}
+
+/// Snapshot of the guild roles and channels Nano uses
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Guild {
+    pub(crate) au_ch_id: serenity::ChannelId,
+    pub(crate) fresher_pg: serenity::RoleId,
+    pub(crate) fresher_ug: serenity::RoleId,
+    pub(crate) gaijin: serenity::RoleId,
+    pub(crate) gn_ch_id: serenity::ChannelId,
+    pub(crate) member: serenity::RoleId,
+    pub(crate) non_member: serenity::RoleId,
+    pub(crate) old_member: serenity::RoleId,
+    pub(crate) server: serenity::GuildId,
+}
+
+impl Guild {
+    pub(crate) fn get(&self, key: Key) -> u64 {
+        match key {
+            Key::AuChannel => self.au_ch_id.get(),
+            Key::GnChannel => self.gn_ch_id.get(),
+            Key::Member => self.member.get(),
+            Key::NonMember => self.non_member.get(),
+            Key::OldMember => self.old_member.get(),
+            Key::FresherPg => self.fresher_pg.get(),
+            Key::FresherUg => self.fresher_ug.get(),
+            Key::Gaijin => self.gaijin.get(),
+            Key::Server => self.server.get(),
+        }
+    }
+
+    /// Fresher role for fresher status, if any
+    pub(crate) fn fresher_role(&self, fresher: Fresher) -> Option<serenity::RoleId> {
+        match fresher {
+            Fresher::No => None,
+            Fresher::YesPg => Some(self.fresher_pg),
+            Fresher::YesUg => Some(self.fresher_ug),
+        }
+    }
+
+    /// Set value of key, ID must be non-zero
+    fn set(&mut self, key: Key, id: u64) {
+        match key {
+            Key::AuChannel => self.au_ch_id = id.into(),
+            Key::GnChannel => self.gn_ch_id = id.into(),
+            Key::Member => self.member = id.into(),
+            Key::NonMember => self.non_member = id.into(),
+            Key::OldMember => self.old_member = id.into(),
+            Key::FresherPg => self.fresher_pg = id.into(),
+            Key::FresherUg => self.fresher_ug = id.into(),
+            Key::Gaijin => self.gaijin = id.into(),
+            Key::Server => self.server = id.into(),
+        }
+    }
+}
+
+/// Guild configuration, with defaults from the environment overridden by the settings
+/// table, shared so changes apply to running handlers without a restart
+#[derive(Clone)]
+pub(crate) struct Config {
+    pool: sqlx::SqlitePool,
+    defaults: Guild,
+    current: Arc<RwLock<Guild>>,
+}
+
+impl Config {
+    /// Read defaults from environment and apply settings from the database
+    pub(crate) async fn from_env(pool: sqlx::SqlitePool) -> Result<Self, Error> {
+        let defaults = Guild {
+            au_ch_id: var!("AU_CHANNEL_ID", _),
+            fresher_pg: var!("FRESHER_PG_ID", _),
+            fresher_ug: var!("FRESHER_UG_ID", _),
+            gaijin: var!("GAIJIN_ID", _),
+            gn_ch_id: var!("GN_CHANNEL_ID", _),
+            member: var!("MEMBER_ID", _),
+            non_member: var!("NON_MEMBER_ID", _),
+            old_member: var!("OLD_MEMBER_ID", _),
+            server: var!("SERVER_ID", _),
+        };
+        let config = Self {
+            pool,
+            defaults,
+            current: Arc::new(RwLock::new(defaults)),
+        };
+        config.load().await?;
+        Ok(config)
+    }
+
+    /// Current guild configuration
+    pub(crate) fn get(&self) -> Guild {
+        *self.current.read().expect("config lock is never poisoned")
+    }
+
+    /// Keys overridden in the settings table
+    pub(crate) async fn overridden(&self) -> Result<Vec<Key>, Error> {
+        Ok(db::get_all_settings(&self.pool)
+            .await?
+            .iter()
+            .filter_map(|s| Key::from_key(&s.key))
+            .collect())
+    }
+
+    /// Rebuild current configuration from defaults and the settings table
+    pub(crate) async fn load(&self) -> Result<(), Error> {
+        let mut guild = self.defaults;
+        for s in db::get_all_settings(&self.pool).await? {
+            match (Key::from_key(&s.key), u64::try_from(s.value)) {
+                (Some(key), Ok(id)) if id != 0 => guild.set(key, id),
+                _ => tracing::warn!("Ignoring invalid setting {} = {}", s.key, s.value),
+            }
+        }
+        *self.current.write().expect("config lock is never poisoned") = guild;
+        Ok(())
+    }
+
+    /// Override key with a non-zero ID
+    pub(crate) async fn set(&self, actor: Actor<'_>, key: Key, id: u64) -> Result<(), Error> {
+        let value = i64::try_from(id)?;
+        db::set_setting(&self.pool, actor, key.key(), value).await?;
+        self.load().await
+    }
+
+    /// Return key to its environment default, false if it was not overridden
+    pub(crate) async fn reset(&self, actor: Actor<'_>, key: Key) -> Result<bool, Error> {
+        let reset = db::delete_setting(&self.pool, actor, key.key()).await?;
+        self.load().await?;
+        Ok(reset)
+    }
+}
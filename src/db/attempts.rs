@@ -0,0 +1,133 @@
+use crate::db::{insert_audit, json};
+use crate::{Actor, Attempts, Error, Flow};
+
+/// Get unix timestamp until which user is locked out of a verification flow, if locked
+pub(crate) async fn get_lockout(
+    pool: &sqlx::SqlitePool,
+    id: i64,
+    flow: Flow,
+) -> Result<Option<i64>, Error> {
+    Ok(sqlx::query!(
+        "select locked_until as \"locked_until!\" from verify_attempts \
+            where discord_id=$1 and flow=$2 and locked_until > unixepoch()",
+        id,
+        flow
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|r| r.locked_until))
+}
+
+/// Record failed verification attempt, counted within a window of `window` seconds
+///
+/// Returns the lockout expiry, `cooldown` seconds away, if this attempt reached `max`
+/// failures, which also starts a fresh window for after the lockout
+pub(crate) async fn record_failed_attempt(
+    pool: &sqlx::SqlitePool,
+    id: i64,
+    flow: Flow,
+    max: i64,
+    window: i64,
+    cooldown: i64,
+) -> Result<Option<i64>, Error> {
+    let mut tx = pool.begin().await?;
+    let failures = sqlx::query!(
+        "insert into verify_attempts (discord_id, flow, failures) values ($1, $2, 1) \
+            on conflict (discord_id, flow) do update set \
+            failures = case when window_start + $3 < unixepoch() then 1 else failures + 1 end, \
+            window_start = case when window_start + $3 < unixepoch() \
+                then unixepoch() else window_start end \
+            returning failures",
+        id,
+        flow,
+        window
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .failures;
+    let locked_until = if failures >= max {
+        let r = sqlx::query!(
+            "update verify_attempts \
+                set failures=0, window_start=unixepoch(), locked_until=unixepoch() + $3 \
+                where discord_id=$1 and flow=$2 returning locked_until as \"locked_until!\"",
+            id,
+            flow,
+            cooldown
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        Some(r.locked_until)
+    } else {
+        None
+    };
+    tx.commit().await?;
+    Ok(locked_until)
+}
+
+/// Clear attempts for a verification flow after a successful verification
+pub(crate) async fn clear_attempts(
+    pool: &sqlx::SqlitePool,
+    id: i64,
+    flow: Flow,
+) -> Result<(), Error> {
+    sqlx::query!(
+        "delete from verify_attempts where discord_id=$1 and flow=$2",
+        id,
+        flow
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Reset attempt counters and lockouts for all verification flows
+pub(crate) async fn reset_attempts(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let attempts = sqlx::query_as!(
+        Attempts,
+        "delete from verify_attempts where discord_id=$1 returning *",
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    if attempts.is_empty() {
+        return Ok(false);
+    }
+    insert_audit(
+        &mut tx,
+        actor,
+        "reset_attempts",
+        Some(id),
+        json(&attempts),
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::test_pool;
+
+    #[tokio::test]
+    async fn lockout_lasts_for_cooldown_not_window() {
+        let pool = test_pool().await;
+        let (window, cooldown) = (24 * 60 * 60, 60);
+        let record = || record_failed_attempt(&pool, 1, Flow::Manual, 2, window, cooldown);
+        assert_eq!(record().await.unwrap(), None);
+        let until = record().await.unwrap().unwrap();
+        let now = poise::serenity_prelude::Timestamp::now().unix_timestamp();
+        assert!((now + cooldown - until).abs() <= 1);
+        assert_eq!(
+            get_lockout(&pool, 1, Flow::Manual).await.unwrap(),
+            Some(until)
+        );
+        assert_eq!(get_lockout(&pool, 1, Flow::Membership).await.unwrap(), None);
+    }
+}
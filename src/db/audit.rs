@@ -0,0 +1,61 @@
+use crate::{Actor, AuditEntry, Error};
+
+/// Serialise database entry for the audit log
+pub(crate) fn json(v: &impl serde::Serialize) -> Option<String> {
+    serde_json::to_string(v).ok()
+}
+
+/// Add entry to audit log, as part of the transaction making the change
+pub(crate) async fn insert_audit(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    action: &str,
+    target: Option<i64>,
+    before: Option<String>,
+    after: Option<String>,
+) -> Result<(), Error> {
+    let actor = actor.to_string();
+    sqlx::query!(
+        "insert into audit_log (actor, action, target, before, after) values ($1, $2, $3, $4, $5)",
+        actor,
+        action,
+        target,
+        before,
+        after
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Get audit log entries older than `before` (an entry ID), newest first, filtered by
+/// target, actor and date range
+pub(crate) async fn get_audit(
+    pool: &sqlx::SqlitePool,
+    target: Option<i64>,
+    actor: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    before: Option<i64>,
+    limit: i64,
+) -> Result<Vec<AuditEntry>, Error> {
+    Ok(sqlx::query_as!(
+        AuditEntry,
+        "select * from audit_log \
+            where ($1 is null or target=$1) \
+            and ($2 is null or actor=$2) \
+            and ($3 is null or created_at >= unixepoch($3)) \
+            and ($4 is null or created_at < unixepoch($4, '+1 day')) \
+            and ($5 is null or id < $5) \
+            order by id desc \
+            limit $6",
+        target,
+        actor,
+        since,
+        until,
+        before,
+        limit
+    )
+    .fetch_all(pool)
+    .await?)
+}
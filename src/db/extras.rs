@@ -1,4 +1,6 @@
-use crate::{Error, Gaijin, FUZZY_THRESHOLD};
+use crate::db::{insert_audit, insert_webhook_event, json};
+use crate::{webhooks::Event, Actor, Error, Gaijin, ManualMember, FUZZY_THRESHOLD};
+use futures::stream::BoxStream;
 
 /// Get count of entries in gaijin table
 pub(crate) async fn count_gaijin(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
@@ -9,12 +11,24 @@ pub(crate) async fn count_gaijin(pool: &sqlx::SqlitePool) -> Result<i64, Error>
 }
 
 /// Delete gaijin by Discord ID
-pub(crate) async fn delete_gaijin_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<bool, Error> {
-    let r = sqlx::query!("delete from gaijin where discord_id=$1", id)
-        .execute(pool)
-        .await?
-        .rows_affected();
-    Ok(r == 1)
+pub(crate) async fn delete_gaijin_by_id(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let g = sqlx::query_as!(
+        Gaijin,
+        "delete from gaijin where discord_id=$1 returning *",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(g) = &g {
+        insert_audit(&mut tx, actor, "delete_gaijin", Some(id), json(g), None).await?;
+    }
+    tx.commit().await?;
+    Ok(g.is_some())
 }
 
 /// Get all entries in gaijin table
@@ -24,6 +38,11 @@ pub(crate) async fn get_all_gaijin(pool: &sqlx::SqlitePool) -> Result<Vec<Gaijin
         .await?)
 }
 
+/// Stream gaijin entries
+pub(crate) fn stream_gaijin(pool: &sqlx::SqlitePool) -> BoxStream<'_, Result<Gaijin, sqlx::Error>> {
+    sqlx::query_as!(Gaijin, "select * from gaijin").fetch(pool)
+}
+
 /// Get gaijin entry by Discord ID
 pub(crate) async fn get_gaijin_by_id(
     pool: &sqlx::SqlitePool,
@@ -71,44 +90,144 @@ pub(crate) async fn get_gaijin_by_name_fuzzy(
 }
 
 /// Add entry to gaijin table
-pub(crate) async fn insert_gaijin(pool: &sqlx::SqlitePool, g: Gaijin) -> Result<(), Error> {
-    sqlx::query!(
-        "insert into gaijin values ($1, $2, $3)",
+pub(crate) async fn insert_gaijin(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    g: Gaijin,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    let event = Event::Gaijin {
+        discord_id: g.discord_id,
+        university: g.university.clone(),
+    };
+    insert_gaijin_tx(&mut tx, actor, g).await?;
+    insert_webhook_event(&mut tx, &event).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Add entry to gaijin table, as part of an existing transaction
+pub(crate) async fn insert_gaijin_tx(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    g: Gaijin,
+) -> Result<(), Error> {
+    let g = sqlx::query_as!(
+        Gaijin,
+        "insert into gaijin values ($1, $2, $3) returning *",
         g.discord_id,
         g.name,
         g.university
     )
-    .execute(pool)
+    .fetch_one(&mut *conn)
     .await?;
+    let id = g.discord_id;
+    insert_audit(conn, actor, "insert_gaijin", Some(id), None, json(&g)).await?;
+    Ok(())
+}
+
+/// Move entry from manual table to gaijin table, leaving manual entry on failure
+pub(crate) async fn insert_gaijin_from_manual(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    g: Gaijin,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    let mm = sqlx::query_as!(
+        ManualMember,
+        "delete from manual where discord_id=$1 returning *",
+        g.discord_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let g = sqlx::query_as!(
+        Gaijin,
+        "insert into gaijin values ($1, $2, $3) returning *",
+        g.discord_id,
+        g.name,
+        g.university
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let (id, before, after) = (g.discord_id, json(&mm), json(&g));
+    insert_audit(
+        &mut tx,
+        actor,
+        "insert_gaijin_from_manual",
+        Some(id),
+        before,
+        after,
+    )
+    .await?;
+    let event = Event::Gaijin {
+        discord_id: id,
+        university: g.university,
+    };
+    insert_webhook_event(&mut tx, &event).await?;
+    tx.commit().await?;
     Ok(())
 }
 
 /// Edit gaijin name field
 pub(crate) async fn edit_gaijin_name(
     pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
     id: i64,
     name: &str,
 ) -> Result<bool, Error> {
-    let r = sqlx::query!("update gaijin set name=$2 where discord_id=$1", id, name)
-        .execute(pool)
-        .await?
-        .rows_affected();
-    Ok(r == 1)
+    let mut tx = pool.begin().await?;
+    let before = sqlx::query_as!(Gaijin, "select * from gaijin where discord_id=$1", id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(after) = sqlx::query_as!(
+        Gaijin,
+        "update gaijin set name=$2 where discord_id=$1 returning *",
+        id,
+        name
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(false);
+    };
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(&mut tx, actor, "edit_gaijin_name", Some(id), before, after).await?;
+    tx.commit().await?;
+    Ok(true)
 }
 
 /// Edit gaijin university field
 pub(crate) async fn edit_gaijin_university(
     pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
     id: i64,
     university: &str,
 ) -> Result<bool, Error> {
-    let r = sqlx::query!(
-        "update gaijin set university=$2 where discord_id=$1",
+    let mut tx = pool.begin().await?;
+    let before = sqlx::query_as!(Gaijin, "select * from gaijin where discord_id=$1", id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(after) = sqlx::query_as!(
+        Gaijin,
+        "update gaijin set university=$2 where discord_id=$1 returning *",
         id,
         university
     )
-    .execute(pool)
+    .fetch_optional(&mut *tx)
     .await?
-    .rows_affected();
-    Ok(r == 1)
+    else {
+        return Ok(false);
+    };
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(
+        &mut tx,
+        actor,
+        "edit_gaijin_university",
+        Some(id),
+        before,
+        after,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(true)
 }
@@ -0,0 +1,17 @@
+use crate::Error;
+
+/// Run trivial query to check database is reachable
+pub(crate) async fn ping(pool: &sqlx::SqlitePool) -> Result<(), Error> {
+    sqlx::query!("select 1 as \"one!: i64\"")
+        .fetch_one(pool)
+        .await?;
+    Ok(())
+}
+
+/// Call fuzzy extension function to check it is loaded
+pub(crate) async fn ping_fuzzy(pool: &sqlx::SqlitePool) -> Result<(), Error> {
+    sqlx::query!("select fuzzy_translit('nano') as \"nano: String\"")
+        .fetch_one(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,461 @@
+use crate::db::{
+    insert_audit, insert_gaijin_tx, insert_manual_tx, insert_member_tx, insert_pending_tx, json,
+};
+use crate::{
+    Actor, Error, Gaijin, ManualMember, Member, MembershipYear, OrderClaim, PendingMember,
+    ReviewRequest, SavedTemplate, Setting,
+};
+use sqlx::Connection as _;
+
+/// Outcome of importing a single row
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Imported {
+    Inserted,
+    Overwritten,
+    /// Row with the same Discord ID already exists and was left unchanged
+    Conflict,
+}
+
+/// Import pending entry within a savepoint, replacing an existing entry if `overwrite`
+pub(crate) async fn import_pending(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    p: PendingMember,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let id = p.discord_id;
+    let exists = sqlx::query!("select discord_id from pending where discord_id=$1", id)
+        .fetch_optional(&mut *sp)
+        .await?
+        .is_some();
+    if exists && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    if exists {
+        let before = sqlx::query_as!(
+            PendingMember,
+            "delete from pending where discord_id=$1 returning *",
+            id
+        )
+        .fetch_one(&mut *sp)
+        .await?;
+        insert_audit(
+            &mut sp,
+            actor,
+            "delete_pending",
+            Some(id),
+            json(&before),
+            None,
+        )
+        .await?;
+    }
+    insert_pending_tx(&mut sp, actor, p).await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+/// Import manual entry within a savepoint, replacing an existing entry if `overwrite`
+pub(crate) async fn import_manual(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    m: ManualMember,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let id = m.discord_id;
+    let exists = sqlx::query!("select discord_id from manual where discord_id=$1", id)
+        .fetch_optional(&mut *sp)
+        .await?
+        .is_some();
+    if exists && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    if exists {
+        let before = sqlx::query_as!(
+            ManualMember,
+            "delete from manual where discord_id=$1 returning *",
+            id
+        )
+        .fetch_one(&mut *sp)
+        .await?;
+        insert_audit(
+            &mut sp,
+            actor,
+            "delete_manual",
+            Some(id),
+            json(&before),
+            None,
+        )
+        .await?;
+    }
+    insert_manual_tx(&mut sp, actor, m).await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+/// Import member within a savepoint, replacing an existing member if `overwrite`
+pub(crate) async fn import_member(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    m: Member,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let id = m.discord_id;
+    let exists = sqlx::query!("select discord_id from members where discord_id=$1", id)
+        .fetch_optional(&mut *sp)
+        .await?
+        .is_some();
+    if exists && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    if exists {
+        let before = sqlx::query_as!(
+            Member,
+            "delete from members where discord_id=$1 returning *",
+            id
+        )
+        .fetch_one(&mut *sp)
+        .await?;
+        insert_audit(
+            &mut sp,
+            actor,
+            "delete_member",
+            Some(id),
+            json(&before),
+            None,
+        )
+        .await?;
+    }
+    insert_member_tx(&mut sp, actor, m).await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+/// Import gaijin entry within a savepoint, replacing an existing entry if `overwrite`
+pub(crate) async fn import_gaijin(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    g: Gaijin,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let id = g.discord_id;
+    let exists = sqlx::query!("select discord_id from gaijin where discord_id=$1", id)
+        .fetch_optional(&mut *sp)
+        .await?
+        .is_some();
+    if exists && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    if exists {
+        let before = sqlx::query_as!(
+            Gaijin,
+            "delete from gaijin where discord_id=$1 returning *",
+            id
+        )
+        .fetch_one(&mut *sp)
+        .await?;
+        insert_audit(
+            &mut sp,
+            actor,
+            "delete_gaijin",
+            Some(id),
+            json(&before),
+            None,
+        )
+        .await?;
+    }
+    insert_gaijin_tx(&mut sp, actor, g).await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+/// Import membership year within a savepoint, replacing the fresher status of an existing
+/// year if `overwrite`
+pub(crate) async fn import_membership_year(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    y: MembershipYear,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let id = y.discord_id;
+    let before = sqlx::query_as!(
+        MembershipYear,
+        "select * from membership_years where discord_id=$1 and year=$2",
+        id,
+        y.year
+    )
+    .fetch_optional(&mut *sp)
+    .await?;
+    if before.is_some() && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    let after = sqlx::query_as!(
+        MembershipYear,
+        "insert into membership_years values ($1, $2, $3) \
+            on conflict (discord_id, year) do update set fresher=excluded.fresher \
+            returning *",
+        id,
+        y.year,
+        y.fresher
+    )
+    .fetch_one(&mut *sp)
+    .await?;
+    let (exists, before, after) = (
+        before.is_some(),
+        before.as_ref().and_then(json),
+        json(&after),
+    );
+    insert_audit(
+        &mut sp,
+        actor,
+        "import_membership_year",
+        Some(id),
+        before,
+        after,
+    )
+    .await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+/// Import order claim within a savepoint, replacing the claimant of an existing claim
+/// if `overwrite`
+pub(crate) async fn import_order_claim(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    c: OrderClaim,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let before = sqlx::query_as!(
+        OrderClaim,
+        "select * from order_claims where order_no=$1",
+        c.order_no
+    )
+    .fetch_optional(&mut *sp)
+    .await?;
+    if before.is_some() && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    let after = sqlx::query_as!(
+        OrderClaim,
+        "insert into order_claims values ($1, $2, $3, $4) \
+            on conflict (order_no) do update set discord_id=excluded.discord_id, \
+            shortcode=excluded.shortcode, claimed_at=excluded.claimed_at \
+            returning *",
+        c.order_no,
+        c.discord_id,
+        c.shortcode,
+        c.claimed_at
+    )
+    .fetch_one(&mut *sp)
+    .await?;
+    let exists = before.is_some();
+    let id = Some(after.discord_id);
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(&mut sp, actor, "import_order_claim", id, before, after).await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+/// Import review request within a savepoint, replacing an existing request if `overwrite`
+///
+/// The manual entry it belongs to must already be imported
+pub(crate) async fn import_review_request(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    r: ReviewRequest,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let id = r.discord_id;
+    let before = sqlx::query_as!(
+        ReviewRequest,
+        "select * from review_requests where discord_id=$1",
+        id
+    )
+    .fetch_optional(&mut *sp)
+    .await?;
+    if before.is_some() && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    let after = sqlx::query_as!(
+        ReviewRequest,
+        "insert into review_requests values ($1, $2, $3, $4, $5) \
+            on conflict (discord_id) do update set url=excluded.url, \
+            channel_id=excluded.channel_id, message_id=excluded.message_id, \
+            created_at=excluded.created_at \
+            returning *",
+        id,
+        r.url,
+        r.channel_id,
+        r.message_id,
+        r.created_at
+    )
+    .fetch_one(&mut *sp)
+    .await?;
+    let exists = before.is_some();
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(
+        &mut sp,
+        actor,
+        "import_review_request",
+        Some(id),
+        before,
+        after,
+    )
+    .await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+/// Import guild setting within a savepoint, replacing an existing value if `overwrite`
+pub(crate) async fn import_setting(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    s: Setting,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let before = sqlx::query_as!(Setting, "select * from settings where key=$1", s.key)
+        .fetch_optional(&mut *sp)
+        .await?;
+    if before.is_some() && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    let after = sqlx::query_as!(
+        Setting,
+        "insert into settings values ($1, $2, $3) \
+            on conflict (key) do update set value=excluded.value, \
+            updated_at=excluded.updated_at \
+            returning *",
+        s.key,
+        s.value,
+        s.updated_at
+    )
+    .fetch_one(&mut *sp)
+    .await?;
+    let exists = before.is_some();
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(&mut sp, actor, "import_setting", None, before, after).await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+/// Import message template within a savepoint, replacing an existing edit if `overwrite`
+pub(crate) async fn import_template(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    t: SavedTemplate,
+    overwrite: bool,
+) -> Result<Imported, Error> {
+    let mut sp = conn.begin().await?;
+    let before = sqlx::query_as!(SavedTemplate, "select * from templates where key=$1", t.key)
+        .fetch_optional(&mut *sp)
+        .await?;
+    if before.is_some() && !overwrite {
+        return Ok(Imported::Conflict);
+    }
+    let after = sqlx::query_as!(
+        SavedTemplate,
+        "insert into templates values ($1, $2, $3) \
+            on conflict (key) do update set body=excluded.body, \
+            updated_at=excluded.updated_at \
+            returning *",
+        t.key,
+        t.body,
+        t.updated_at
+    )
+    .fetch_one(&mut *sp)
+    .await?;
+    let exists = before.is_some();
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(&mut sp, actor, "import_template", None, before, after).await?;
+    sp.commit().await?;
+    Ok(if exists {
+        Imported::Overwritten
+    } else {
+        Imported::Inserted
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{get_membership_years_by_id, test_pool};
+    use crate::{Fresher, Method};
+
+    #[tokio::test]
+    async fn import_restores_membership_years_only_from_dump() {
+        let pool = test_pool().await;
+        let mut tx = pool.begin().await.unwrap();
+        let m = Member {
+            discord_id: 1,
+            shortcode: "ab123".to_string(),
+            nickname: "Nick".to_string(),
+            realname: "Real Name".to_string(),
+            fresher: Fresher::YesUg,
+            verified_at: Some(0),
+            method: Method::Login,
+            verified_by: None,
+        };
+        let imported = import_member(&mut tx, Actor::System, m, false).await;
+        assert!(matches!(imported.unwrap(), Imported::Inserted));
+        tx.commit().await.unwrap();
+        assert!(get_membership_years_by_id(&pool, 1)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let year = |fresher| MembershipYear {
+            discord_id: 1,
+            year: 2023,
+            fresher,
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let first = import_membership_year(&mut tx, Actor::System, year(Fresher::YesUg), false);
+        assert!(matches!(first.await.unwrap(), Imported::Inserted));
+        let again = import_membership_year(&mut tx, Actor::System, year(Fresher::No), false);
+        assert!(matches!(again.await.unwrap(), Imported::Conflict));
+        tx.commit().await.unwrap();
+        let years = get_membership_years_by_id(&pool, 1).await.unwrap();
+        assert_eq!(years.len(), 1);
+        assert!(matches!(years[0].fresher, Fresher::YesUg));
+    }
+}
@@ -0,0 +1,106 @@
+use crate::db::{insert_audit, json};
+use crate::{Actor, ApiKey, Error};
+
+/// Get all API keys
+pub(crate) async fn get_all_api_keys(pool: &sqlx::SqlitePool) -> Result<Vec<ApiKey>, Error> {
+    Ok(
+        sqlx::query_as!(ApiKey, "select * from api_keys order by name")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+/// Get API key by hash of the key
+pub(crate) async fn get_api_key_by_hash(
+    pool: &sqlx::SqlitePool,
+    hash: &str,
+) -> Result<Option<ApiKey>, Error> {
+    Ok(
+        sqlx::query_as!(ApiKey, "select * from api_keys where key_hash=$1", hash)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// Get API key by name
+pub(crate) async fn get_api_key_by_name(
+    pool: &sqlx::SqlitePool,
+    name: &str,
+) -> Result<Option<ApiKey>, Error> {
+    Ok(
+        sqlx::query_as!(ApiKey, "select * from api_keys where name=$1", name)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// Add API key
+pub(crate) async fn insert_api_key(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    name: &str,
+    hash: &str,
+    scopes: &str,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    let k = sqlx::query_as!(
+        ApiKey,
+        "insert into api_keys (name, key_hash, scopes) values ($1, $2, $3) returning *",
+        name,
+        hash,
+        scopes
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    insert_audit(&mut tx, actor, "insert_api_key", None, None, json(&k)).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete API key by name
+pub(crate) async fn delete_api_key(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    name: &str,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let Some(k) = sqlx::query_as!(
+        ApiKey,
+        "delete from api_keys where name=$1 returning *",
+        name
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(false);
+    };
+    insert_audit(&mut tx, actor, "delete_api_key", None, json(&k), None).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Record signature of a signed request until `expires_at`, pruning expired signatures,
+/// false if the client already used it
+pub(crate) async fn insert_signed_request(
+    pool: &sqlx::SqlitePool,
+    client: &str,
+    signature: &str,
+    expires_at: i64,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("delete from signed_requests where expires_at < unixepoch()")
+        .execute(&mut *tx)
+        .await?;
+    let inserted = sqlx::query!(
+        "insert or ignore into signed_requests (client, signature, expires_at) \
+            values ($1, $2, $3)",
+        client,
+        signature,
+        expires_at
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok(inserted == 1)
+}
@@ -0,0 +1,62 @@
+use crate::{Error, LookupEntry, Member};
+
+/// Get members by Discord IDs, skipping IDs that are not members
+pub(crate) async fn get_members_by_ids(
+    pool: &sqlx::SqlitePool,
+    ids: &[i64],
+) -> Result<Vec<Member>, Error> {
+    let ids = serde_json::to_string(ids)?;
+    Ok(sqlx::query_as!(
+        Member,
+        "select * from members where discord_id in (select value from json_each($1))",
+        ids
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Record which Discord IDs a client looked up, and prune entries older than 90 days
+pub(crate) async fn insert_lookup_log(
+    pool: &sqlx::SqlitePool,
+    client: &str,
+    lookups: &[(i64, bool)],
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("delete from lookup_log where created_at < unixepoch('now', '-90 days')")
+        .execute(&mut *tx)
+        .await?;
+    for (id, verified) in lookups {
+        sqlx::query!(
+            "insert into lookup_log (client, discord_id, verified) values ($1, $2, $3)",
+            client,
+            id,
+            verified
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Get lookup log entries, newest first, filtered by client and Discord ID
+pub(crate) async fn get_lookup_log(
+    pool: &sqlx::SqlitePool,
+    client: Option<String>,
+    target: Option<i64>,
+    limit: i64,
+) -> Result<Vec<LookupEntry>, Error> {
+    Ok(sqlx::query_as!(
+        LookupEntry,
+        "select * from lookup_log \
+            where ($1 is null or client=$1) \
+            and ($2 is null or discord_id=$2) \
+            order by id desc \
+            limit $3",
+        client,
+        target,
+        limit
+    )
+    .fetch_all(pool)
+    .await?)
+}
@@ -1,4 +1,6 @@
-use crate::{Error, ManualMember};
+use crate::db::{insert_audit, insert_webhook_event, json};
+use crate::{webhooks::Event, Actor, Error, Fresher, ManualMember, PendingMember};
+use futures::stream::BoxStream;
 
 /// Get count of entries in manual table
 pub(crate) async fn count_manual(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
@@ -9,12 +11,49 @@ pub(crate) async fn count_manual(pool: &sqlx::SqlitePool) -> Result<i64, Error>
 }
 
 /// Delete manual by Discord ID
-pub(crate) async fn delete_manual_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<bool, Error> {
-    let r = sqlx::query!("delete from manual where discord_id=$1", id)
-        .execute(pool)
-        .await?
-        .rows_affected();
-    Ok(r == 1)
+pub(crate) async fn delete_manual_by_id(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let deleted = delete_manual_tx(&mut tx, actor, id).await?;
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+/// Deny manual request by deleting it
+pub(crate) async fn deny_manual_by_id(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let deleted = delete_manual_tx(&mut tx, actor, id).await?;
+    if deleted {
+        insert_webhook_event(&mut tx, &Event::Denied { discord_id: id }).await?;
+    }
+    tx.commit().await?;
+    Ok(deleted)
+}
+
+/// Delete manual by Discord ID, as part of an existing transaction
+pub(crate) async fn delete_manual_tx(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<bool, Error> {
+    let m = sqlx::query_as!(
+        ManualMember,
+        "delete from manual where discord_id=$1 returning *",
+        id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(m) = &m {
+        insert_audit(conn, actor, "delete_manual", Some(id), json(m), None).await?;
+    }
+    Ok(m.is_some())
 }
 
 /// Get all entries in manual table
@@ -24,6 +63,19 @@ pub(crate) async fn get_all_manual(pool: &sqlx::SqlitePool) -> Result<Vec<Manual
         .await?)
 }
 
+/// Stream manual entries, optionally filtered by fresher status
+pub(crate) fn stream_manual(
+    pool: &sqlx::SqlitePool,
+    fresher: Option<Fresher>,
+) -> BoxStream<'_, Result<ManualMember, sqlx::Error>> {
+    sqlx::query_as!(
+        ManualMember,
+        "select * from manual where ($1 is null or fresher=$1)",
+        fresher
+    )
+    .fetch(pool)
+}
+
 /// Get manual entry by Discord ID
 pub(crate) async fn get_manual_by_id(
     pool: &sqlx::SqlitePool,
@@ -37,25 +89,116 @@ pub(crate) async fn get_manual_by_id(
 }
 
 /// Add manual entry to manual table
-pub(crate) async fn insert_manual(pool: &sqlx::SqlitePool, m: ManualMember) -> Result<(), Error> {
+pub(crate) async fn insert_manual(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    m: ManualMember,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    insert_manual_tx(&mut tx, actor, m).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Add entry to manual table, as part of an existing transaction
+pub(crate) async fn insert_manual_tx(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    m: ManualMember,
+) -> Result<(), Error> {
     let shortcode = m.shortcode.to_lowercase();
-    sqlx::query!(
-        "insert into manual values ($1,$2,$3,$4,$5)",
+    let m = sqlx::query_as!(
+        ManualMember,
+        "insert into manual values ($1,$2,$3,$4,$5) returning *",
         m.discord_id,
         shortcode,
         m.nickname,
         m.realname,
         m.fresher
     )
-    .execute(pool)
+    .fetch_one(&mut *conn)
     .await?;
+    let id = m.discord_id;
+    insert_audit(conn, actor, "insert_manual", Some(id), None, json(&m)).await?;
+    Ok(())
+}
+
+/// Add manual entry to manual table, replacing any pending entry
+pub(crate) async fn insert_manual_replacing_pending(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    m: ManualMember,
+) -> Result<(), Error> {
+    let shortcode = m.shortcode.to_lowercase();
+    let mut tx = pool.begin().await?;
+    let p = sqlx::query_as!(
+        PendingMember,
+        "delete from pending where discord_id=$1 returning *",
+        m.discord_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let m = sqlx::query_as!(
+        ManualMember,
+        "insert into manual values ($1,$2,$3,$4,$5) returning *",
+        m.discord_id,
+        shortcode,
+        m.nickname,
+        m.realname,
+        m.fresher
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let (id, before, after) = (m.discord_id, p.as_ref().and_then(json), json(&m));
+    insert_audit(&mut tx, actor, "insert_manual", Some(id), before, after).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete pending and manual entries by Discord ID
+pub(crate) async fn delete_pending_and_manual_by_id(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    let p = sqlx::query_as!(
+        PendingMember,
+        "delete from pending where discord_id=$1 returning *",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(p) = &p {
+        insert_audit(&mut tx, actor, "delete_pending", Some(id), json(p), None).await?;
+    }
+    let m = sqlx::query_as!(
+        ManualMember,
+        "delete from manual where discord_id=$1 returning *",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(m) = &m {
+        insert_audit(&mut tx, actor, "delete_manual", Some(id), json(m), None).await?;
+    }
+    tx.commit().await?;
     Ok(())
 }
 
 /// Delete all entries in manual table
-pub(crate) async fn delete_all_manual(pool: &sqlx::SqlitePool) -> Result<u64, Error> {
-    Ok(sqlx::query!("delete from manual")
-        .execute(pool)
-        .await?
-        .rows_affected())
+pub(crate) async fn delete_all_manual(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+) -> Result<u64, Error> {
+    let mut tx = pool.begin().await?;
+    let deleted = sqlx::query_as!(ManualMember, "delete from manual returning *")
+        .fetch_all(&mut *tx)
+        .await?;
+    for m in &deleted {
+        let id = m.discord_id;
+        insert_audit(&mut tx, actor, "delete_all_manual", Some(id), json(m), None).await?;
+    }
+    tx.commit().await?;
+    Ok(deleted.len() as u64)
 }
@@ -1,4 +1,11 @@
-use crate::{Error, Fresher, ManualMember, Member, PendingMember, FUZZY_THRESHOLD};
+use crate::db::{
+    delete_manual_tx, insert_audit, insert_membership_year, insert_webhook_event, json,
+};
+use crate::{
+    webhooks::Event, Actor, Error, Fresher, ManualMember, Member, Method, PendingMember,
+    FUZZY_THRESHOLD,
+};
+use futures::stream::BoxStream;
 
 /// Get count of entries in members table
 pub(crate) async fn count_members(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
@@ -9,12 +16,25 @@ pub(crate) async fn count_members(pool: &sqlx::SqlitePool) -> Result<i64, Error>
 }
 
 /// Delete member by Discord ID
-pub(crate) async fn delete_member_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<bool, Error> {
-    let r = sqlx::query!("delete from members where discord_id=$1", id)
-        .execute(pool)
-        .await?
-        .rows_affected();
-    Ok(r == 1)
+pub(crate) async fn delete_member_by_id(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let m = sqlx::query_as!(
+        Member,
+        "delete from members where discord_id=$1 returning *",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(m) = &m {
+        insert_audit(&mut tx, actor, "delete_member", Some(id), json(m), None).await?;
+        insert_webhook_event(&mut tx, &Event::Deleted { discord_id: id }).await?;
+    }
+    tx.commit().await?;
+    Ok(m.is_some())
 }
 
 /// Get all entries in members table
@@ -24,6 +44,22 @@ pub(crate) async fn get_all_members(pool: &sqlx::SqlitePool) -> Result<Vec<Membe
         .await?)
 }
 
+/// Stream members, optionally filtered by fresher status and verified on or after a date
+pub(crate) fn stream_members<'a>(
+    pool: &'a sqlx::SqlitePool,
+    fresher: Option<Fresher>,
+    verified_after: Option<&str>,
+) -> BoxStream<'a, Result<Member, sqlx::Error>> {
+    sqlx::query_as!(
+        Member,
+        "select * from members where ($1 is null or fresher=$1) \
+            and ($2 is null or verified_at >= unixepoch($2))",
+        fresher,
+        verified_after
+    )
+    .fetch(pool)
+}
+
 /// Get member entry by Discord ID
 pub(crate) async fn get_member_by_id(
     pool: &sqlx::SqlitePool,
@@ -119,147 +155,439 @@ pub(crate) async fn get_member_by_realname_fuzzy(
 }
 
 /// Add member entry to members table
-pub(crate) async fn insert_member(pool: &sqlx::SqlitePool, m: Member) -> Result<(), Error> {
+pub(crate) async fn insert_member(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    m: Member,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    let event = Event::Verified {
+        discord_id: m.discord_id,
+        method: m.method,
+        fresher: m.fresher,
+    };
+    let (id, fresher) = (m.discord_id, m.fresher);
+    insert_member_tx(&mut tx, actor, m).await?;
+    insert_membership_year(&mut tx, id, fresher).await?;
+    insert_webhook_event(&mut tx, &event).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Insert member, as part of an existing transaction, without recording a membership
+/// year as imports restore those separately
+pub(crate) async fn insert_member_tx(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    m: Member,
+) -> Result<(), Error> {
     let shortcode = m.shortcode.to_lowercase();
-    sqlx::query!(
-        "insert into members values ($1, $2, $3, $4, $5)",
+    let m = sqlx::query_as!(
+        Member,
+        "insert into members \
+            (discord_id, shortcode, nickname, realname, fresher, verified_at, method, verified_by) \
+            values ($1, $2, $3, $4, $5, $6, $7, $8) returning *",
         m.discord_id,
         shortcode,
         m.nickname,
         m.realname,
-        m.fresher
+        m.fresher,
+        m.verified_at,
+        m.method,
+        m.verified_by
     )
-    .execute(pool)
+    .fetch_one(&mut *conn)
     .await?;
+    let id = m.discord_id;
+    insert_audit(conn, actor, "insert_member", Some(id), None, json(&m)).await?;
     Ok(())
 }
 
-/// Add member entry to members table from pending table
+/// Move entry from pending table to members table, also removing any manual request,
+/// leaving both entries on failure
 pub(crate) async fn insert_member_from_pending(
     pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
     id: i64,
     nickname: &str,
     fresher: Fresher,
+    method: Method,
+    verified_by: Option<i64>,
 ) -> Result<Member, Error> {
+    let mut tx = pool.begin().await?;
     let p = sqlx::query_as!(
         PendingMember,
         "delete from pending where discord_id=$1 returning *",
         id
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
     let m = sqlx::query_as!(
         Member,
-        "insert into members values ($1, $2, $3, $4, $5) returning *",
+        "insert into members \
+            (discord_id, shortcode, nickname, realname, fresher, verified_at, method, verified_by) \
+            values ($1, $2, $3, $4, $5, unixepoch(), $6, $7) returning *",
         id,
         p.shortcode,
         nickname,
         p.realname,
-        fresher
+        fresher,
+        method,
+        verified_by
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    insert_membership_year(&mut tx, id, m.fresher).await?;
+    delete_manual_tx(&mut tx, actor, id).await?;
+    let (before, after) = (json(&p), json(&m));
+    insert_audit(
+        &mut tx,
+        actor,
+        "insert_member_from_pending",
+        Some(id),
+        before,
+        after,
     )
-    .fetch_one(pool)
     .await?;
+    let event = Event::Verified {
+        discord_id: id,
+        method,
+        fresher,
+    };
+    insert_webhook_event(&mut tx, &event).await?;
+    tx.commit().await?;
     Ok(m)
 }
 
-/// Add member entry to members table from manual table
+/// Move entry from manual table to members table, leaving manual entry on failure
 pub(crate) async fn insert_member_from_manual(
     pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
     id: i64,
+    verified_by: Option<i64>,
 ) -> Result<Member, Error> {
+    let mut tx = pool.begin().await?;
     let mm = sqlx::query_as!(
         ManualMember,
         "delete from manual where discord_id=$1 returning *",
         id
     )
-    .fetch_one(pool)
+    .fetch_one(&mut *tx)
     .await?;
     let m = sqlx::query_as!(
         Member,
-        "insert into members values ($1, $2, $3, $4, $5) returning *",
+        "insert into members \
+            (discord_id, shortcode, nickname, realname, fresher, verified_at, method, verified_by) \
+            values ($1, $2, $3, $4, $5, unixepoch(), $6, $7) returning *",
         id,
         mm.shortcode,
         mm.nickname,
         mm.realname,
-        mm.fresher
+        mm.fresher,
+        Method::Manual,
+        verified_by
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    insert_membership_year(&mut tx, id, m.fresher).await?;
+    let (before, after) = (json(&mm), json(&m));
+    insert_audit(
+        &mut tx,
+        actor,
+        "insert_member_from_manual",
+        Some(id),
+        before,
+        after,
     )
-    .fetch_one(pool)
     .await?;
+    let event = Event::Verified {
+        discord_id: id,
+        method: Method::Manual,
+        fresher: m.fresher,
+    };
+    insert_webhook_event(&mut tx, &event).await?;
+    tx.commit().await?;
     Ok(m)
 }
 
 /// Edit member shortcode field
 pub(crate) async fn edit_member_shortcode(
     pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
     id: i64,
     shortcode: &str,
 ) -> Result<bool, Error> {
-    let r = sqlx::query!(
-        "update members set shortcode=$2 where discord_id=$1",
+    let mut tx = pool.begin().await?;
+    let before = sqlx::query_as!(Member, "select * from members where discord_id=$1", id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(after) = sqlx::query_as!(
+        Member,
+        "update members set shortcode=$2 where discord_id=$1 returning *",
         id,
         shortcode
     )
-    .execute(pool)
+    .fetch_optional(&mut *tx)
     .await?
-    .rows_affected();
-    Ok(r == 1)
+    else {
+        return Ok(false);
+    };
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(
+        &mut tx,
+        actor,
+        "edit_member_shortcode",
+        Some(id),
+        before,
+        after,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(true)
 }
 
 /// Edit member nickname field
 pub(crate) async fn edit_member_nickname(
     pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
     id: i64,
     nickname: &str,
 ) -> Result<bool, Error> {
-    let r = sqlx::query!(
-        "update members set nickname=$2 where discord_id=$1",
+    let mut tx = pool.begin().await?;
+    let before = sqlx::query_as!(Member, "select * from members where discord_id=$1", id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(after) = sqlx::query_as!(
+        Member,
+        "update members set nickname=$2 where discord_id=$1 returning *",
         id,
         nickname
     )
-    .execute(pool)
+    .fetch_optional(&mut *tx)
     .await?
-    .rows_affected();
-    Ok(r == 1)
+    else {
+        return Ok(false);
+    };
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(
+        &mut tx,
+        actor,
+        "edit_member_nickname",
+        Some(id),
+        before,
+        after,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(true)
 }
 
 /// Edit member realname field
 pub(crate) async fn edit_member_realname(
     pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
     id: i64,
     realname: &str,
 ) -> Result<bool, Error> {
-    let r = sqlx::query!(
-        "update members set realname=$2 where discord_id=$1",
+    let mut tx = pool.begin().await?;
+    let before = sqlx::query_as!(Member, "select * from members where discord_id=$1", id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(after) = sqlx::query_as!(
+        Member,
+        "update members set realname=$2 where discord_id=$1 returning *",
         id,
         realname
     )
-    .execute(pool)
+    .fetch_optional(&mut *tx)
     .await?
-    .rows_affected();
-    Ok(r == 1)
+    else {
+        return Ok(false);
+    };
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(
+        &mut tx,
+        actor,
+        "edit_member_realname",
+        Some(id),
+        before,
+        after,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(true)
 }
 
 /// Edit member fresher field
 pub(crate) async fn edit_member_fresher(
     pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
     id: i64,
     fresher: Fresher,
 ) -> Result<bool, Error> {
-    let r = sqlx::query!(
-        "update members set fresher=$2 where discord_id=$1",
+    let mut tx = pool.begin().await?;
+    let before = sqlx::query_as!(Member, "select * from members where discord_id=$1", id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let Some(after) = sqlx::query_as!(
+        Member,
+        "update members set fresher=$2 where discord_id=$1 returning *",
         id,
         fresher
     )
-    .execute(pool)
+    .fetch_optional(&mut *tx)
     .await?
-    .rows_affected();
-    Ok(r == 1)
+    else {
+        return Ok(false);
+    };
+    let (before, after) = (before.as_ref().and_then(json), json(&after));
+    insert_audit(
+        &mut tx,
+        actor,
+        "edit_member_fresher",
+        Some(id),
+        before,
+        after,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(true)
 }
 
 /// Set all members to non-freshers
-pub(crate) async fn set_members_non_fresher(pool: &sqlx::SqlitePool) -> Result<u64, Error> {
-    Ok(sqlx::query!("update members set fresher='no'")
-        .execute(pool)
+pub(crate) async fn set_members_non_fresher(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+) -> Result<u64, Error> {
+    let mut tx = pool.begin().await?;
+    let freshers = sqlx::query_as!(Member, "select * from members where fresher!='no'")
+        .fetch_all(&mut *tx)
+        .await?;
+    let r = sqlx::query!("update members set fresher='no'")
+        .execute(&mut *tx)
         .await?
-        .rows_affected())
+        .rows_affected();
+    for m in freshers {
+        let (id, before) = (m.discord_id, json(&m));
+        let after = Member {
+            fresher: Fresher::No,
+            ..m
+        };
+        insert_audit(
+            &mut tx,
+            actor,
+            "set_members_non_fresher",
+            Some(id),
+            before,
+            json(&after),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, test_pool};
+
+    async fn taken_shortcode(pool: &sqlx::SqlitePool) {
+        let m = Member {
+            discord_id: 1,
+            shortcode: "ab123".to_string(),
+            nickname: "Taken".to_string(),
+            realname: "Taken Name".to_string(),
+            fresher: Fresher::No,
+            verified_at: None,
+            method: Method::Manual,
+            verified_by: None,
+        };
+        insert_member(pool, Actor::System, m).await.unwrap();
+    }
+
+    fn pending(shortcode: &str) -> PendingMember {
+        PendingMember {
+            discord_id: 2,
+            shortcode: shortcode.to_string(),
+            realname: "Pending Name".to_string(),
+        }
+    }
+
+    fn manual(shortcode: &str) -> ManualMember {
+        ManualMember {
+            discord_id: 2,
+            shortcode: shortcode.to_string(),
+            nickname: "Manual".to_string(),
+            realname: "Manual Name".to_string(),
+            fresher: Fresher::YesUg,
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_pending_promotion_keeps_pending_and_manual() {
+        let pool = test_pool().await;
+        taken_shortcode(&pool).await;
+        db::insert_manual(&pool, Actor::System, manual("cd456"))
+            .await
+            .unwrap();
+        db::insert_pending(&pool, Actor::System, pending("ab123"))
+            .await
+            .unwrap();
+
+        let promoted = insert_member_from_pending(
+            &pool,
+            Actor::System,
+            2,
+            "Nick",
+            Fresher::No,
+            Method::Login,
+            None,
+        )
+        .await;
+        assert!(promoted.is_err());
+        assert!(db::get_pending_by_id(&pool, 2).await.unwrap().is_some());
+        assert!(db::get_manual_by_id(&pool, 2).await.unwrap().is_some());
+        assert!(get_member_by_id(&pool, 2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn pending_promotion_removes_manual() {
+        let pool = test_pool().await;
+        db::insert_manual(&pool, Actor::System, manual("cd456"))
+            .await
+            .unwrap();
+        db::insert_pending(&pool, Actor::System, pending("cd456"))
+            .await
+            .unwrap();
+
+        insert_member_from_pending(
+            &pool,
+            Actor::System,
+            2,
+            "Nick",
+            Fresher::No,
+            Method::Login,
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(db::get_pending_by_id(&pool, 2).await.unwrap().is_none());
+        assert!(db::get_manual_by_id(&pool, 2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn failed_manual_promotion_keeps_manual() {
+        let pool = test_pool().await;
+        taken_shortcode(&pool).await;
+        db::insert_manual(&pool, Actor::System, manual("ab123"))
+            .await
+            .unwrap();
+
+        assert!(insert_member_from_manual(&pool, Actor::System, 2, None)
+            .await
+            .is_err());
+        assert!(db::get_manual_by_id(&pool, 2).await.unwrap().is_some());
+        assert!(get_member_by_id(&pool, 2).await.unwrap().is_none());
+    }
 }
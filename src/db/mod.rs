@@ -9,3 +9,54 @@ pub(crate) use manual::*;
 
 pub(crate) mod extras;
 pub(crate) use extras::*;
+
+pub(crate) mod audit;
+pub(crate) use audit::*;
+
+pub(crate) mod years;
+pub(crate) use years::*;
+
+pub(crate) mod orders;
+pub(crate) use orders::*;
+
+pub(crate) mod attempts;
+pub(crate) use attempts::*;
+
+pub(crate) mod states;
+pub(crate) use states::*;
+
+pub(crate) mod keys;
+pub(crate) use keys::*;
+
+pub(crate) mod import;
+pub(crate) use import::*;
+
+pub(crate) mod health;
+pub(crate) use health::*;
+
+pub(crate) mod webhooks;
+pub(crate) use webhooks::*;
+
+pub(crate) mod lookups;
+pub(crate) use lookups::*;
+
+pub(crate) mod reviews;
+pub(crate) use reviews::*;
+
+pub(crate) mod settings;
+pub(crate) use settings::*;
+
+pub(crate) mod templates;
+pub(crate) use templates::*;
+
+/// Empty in-memory database with migrations applied
+#[cfg(test)]
+pub(crate) async fn test_pool() -> sqlx::SqlitePool {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("in-memory database opens");
+    sqlx::migrate!().run(&pool).await.expect("migrations apply");
+    pool
+}
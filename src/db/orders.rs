@@ -0,0 +1,77 @@
+use crate::db::{
+    insert_audit, insert_member_tx, insert_membership_year, insert_webhook_event, json,
+};
+use crate::{webhooks::Event, Actor, Error, Member, Method, OrderClaim};
+use futures::stream::BoxStream;
+
+/// Get count of claimed order numbers
+pub(crate) async fn count_order_claims(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
+    Ok(
+        sqlx::query!("select count(*) as \"i64!\" from order_claims")
+            .fetch_one(pool)
+            .await?
+            .i64,
+    )
+}
+
+/// Stream all claimed order numbers
+pub(crate) fn stream_order_claims(
+    pool: &sqlx::SqlitePool,
+) -> BoxStream<'_, Result<OrderClaim, sqlx::Error>> {
+    sqlx::query_as!(OrderClaim, "select * from order_claims order by order_no").fetch(pool)
+}
+
+/// Insert member and claim their union order number
+///
+/// Returns the Discord ID of the existing claimant without inserting if the order
+/// has already been claimed by a different account
+pub(crate) async fn insert_member_claiming_order(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    m: Member,
+    order_no: i64,
+) -> Result<Option<i64>, Error> {
+    let mut tx = pool.begin().await?;
+    if let Some(claim) = sqlx::query!(
+        "select discord_id from order_claims where order_no=$1",
+        order_no
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    {
+        if claim.discord_id != m.discord_id {
+            return Ok(Some(claim.discord_id));
+        }
+    } else {
+        let shortcode = m.shortcode.to_lowercase();
+        sqlx::query!(
+            "insert into order_claims (order_no, discord_id, shortcode) values ($1, $2, $3)",
+            order_no,
+            m.discord_id,
+            shortcode
+        )
+        .execute(&mut *tx)
+        .await?;
+        let after = json(&serde_json::json!({ "order_no": order_no, "shortcode": shortcode }));
+        insert_audit(
+            &mut tx,
+            actor,
+            "claim_order",
+            Some(m.discord_id),
+            None,
+            after,
+        )
+        .await?;
+    }
+    let (id, fresher) = (m.discord_id, m.fresher);
+    insert_member_tx(&mut tx, actor, m).await?;
+    insert_membership_year(&mut tx, id, fresher).await?;
+    let event = Event::Verified {
+        discord_id: id,
+        method: Method::Membership,
+        fresher,
+    };
+    insert_webhook_event(&mut tx, &event).await?;
+    tx.commit().await?;
+    Ok(None)
+}
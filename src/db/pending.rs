@@ -1,4 +1,6 @@
-use crate::{Error, PendingMember};
+use crate::db::{insert_audit, json};
+use crate::{Actor, Error, PendingMember};
+use futures::stream::BoxStream;
 
 /// Get count of entries in pending table
 pub(crate) async fn count_pending(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
@@ -9,12 +11,24 @@ pub(crate) async fn count_pending(pool: &sqlx::SqlitePool) -> Result<i64, Error>
 }
 
 /// Delete pending by Discord ID
-pub(crate) async fn delete_pending_by_id(pool: &sqlx::SqlitePool, id: i64) -> Result<bool, Error> {
-    let r = sqlx::query!("delete from pending where discord_id=$1", id)
-        .execute(pool)
-        .await?
-        .rows_affected();
-    Ok(r == 1)
+pub(crate) async fn delete_pending_by_id(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let p = sqlx::query_as!(
+        PendingMember,
+        "delete from pending where discord_id=$1 returning *",
+        id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(p) = &p {
+        insert_audit(&mut tx, actor, "delete_pending", Some(id), json(p), None).await?;
+    }
+    tx.commit().await?;
+    Ok(p.is_some())
 }
 
 /// Get all entries in pending table
@@ -24,6 +38,13 @@ pub(crate) async fn get_all_pending(pool: &sqlx::SqlitePool) -> Result<Vec<Pendi
         .await?)
 }
 
+/// Stream pending entries
+pub(crate) fn stream_pending(
+    pool: &sqlx::SqlitePool,
+) -> BoxStream<'_, Result<PendingMember, sqlx::Error>> {
+    sqlx::query_as!(PendingMember, "select * from pending").fetch(pool)
+}
+
 /// Get pending entry by Discord ID
 pub(crate) async fn get_pending_by_id(
     pool: &sqlx::SqlitePool,
@@ -39,23 +60,59 @@ pub(crate) async fn get_pending_by_id(
 }
 
 /// Add pending entry to pending table
-pub(crate) async fn insert_pending(pool: &sqlx::SqlitePool, p: PendingMember) -> Result<(), Error> {
+pub(crate) async fn insert_pending(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    p: PendingMember,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    insert_pending_tx(&mut tx, actor, p).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Add entry to pending table, as part of an existing transaction
+pub(crate) async fn insert_pending_tx(
+    conn: &mut sqlx::SqliteConnection,
+    actor: Actor<'_>,
+    p: PendingMember,
+) -> Result<(), Error> {
     let shortcode = p.shortcode.to_lowercase();
-    sqlx::query!(
-        "insert into pending values ($1, $2, $3)",
+    let p = sqlx::query_as!(
+        PendingMember,
+        "insert into pending values ($1, $2, $3) returning *",
         p.discord_id,
         shortcode,
         p.realname
     )
-    .execute(pool)
+    .fetch_one(&mut *conn)
     .await?;
+    let id = p.discord_id;
+    insert_audit(conn, actor, "insert_pending", Some(id), None, json(&p)).await?;
     Ok(())
 }
 
 /// Delete all entries in pending table
-pub(crate) async fn delete_all_pending(pool: &sqlx::SqlitePool) -> Result<u64, Error> {
-    Ok(sqlx::query!("delete from pending")
-        .execute(pool)
-        .await?
-        .rows_affected())
+pub(crate) async fn delete_all_pending(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+) -> Result<u64, Error> {
+    let mut tx = pool.begin().await?;
+    let deleted = sqlx::query_as!(PendingMember, "delete from pending returning *")
+        .fetch_all(&mut *tx)
+        .await?;
+    for p in &deleted {
+        let id = p.discord_id;
+        insert_audit(
+            &mut tx,
+            actor,
+            "delete_all_pending",
+            Some(id),
+            json(p),
+            None,
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(deleted.len() as u64)
 }
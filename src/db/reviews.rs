@@ -0,0 +1,72 @@
+use crate::{Error, ReviewRequest};
+use futures::stream::BoxStream;
+
+/// Get count of review requests
+pub(crate) async fn count_review_requests(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
+    Ok(
+        sqlx::query!("select count(*) as \"i64!\" from review_requests")
+            .fetch_one(pool)
+            .await?
+            .i64,
+    )
+}
+
+/// Stream all review requests
+pub(crate) fn stream_review_requests(
+    pool: &sqlx::SqlitePool,
+) -> BoxStream<'_, Result<ReviewRequest, sqlx::Error>> {
+    sqlx::query_as!(
+        ReviewRequest,
+        "select * from review_requests order by discord_id"
+    )
+    .fetch(pool)
+}
+
+/// Get all review requests, for entries in the manual table
+pub(crate) async fn get_all_review_requests(
+    pool: &sqlx::SqlitePool,
+) -> Result<Vec<ReviewRequest>, Error> {
+    Ok(
+        sqlx::query_as!(ReviewRequest, "select * from review_requests")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+/// Get review request by Discord ID
+pub(crate) async fn get_review_request_by_id(
+    pool: &sqlx::SqlitePool,
+    id: i64,
+) -> Result<Option<ReviewRequest>, Error> {
+    Ok(sqlx::query_as!(
+        ReviewRequest,
+        "select * from review_requests where discord_id=$1",
+        id
+    )
+    .fetch_optional(pool)
+    .await?)
+}
+
+/// Store evidence URL and review message for a manual entry, removed with the entry
+pub(crate) async fn insert_review_request(
+    pool: &sqlx::SqlitePool,
+    id: i64,
+    url: &str,
+    channel_id: Option<i64>,
+    message_id: Option<i64>,
+) -> Result<(), Error> {
+    sqlx::query!(
+        "insert into review_requests (discord_id, url, channel_id, message_id) \
+            values ($1, $2, $3, $4) \
+            on conflict (discord_id) do update set url=excluded.url, \
+            channel_id=excluded.channel_id, message_id=excluded.message_id, \
+            created_at=excluded.created_at",
+        id,
+        url,
+        channel_id,
+        message_id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use crate::db::{insert_audit, json};
+use crate::{Actor, Error, Setting};
+use futures::stream::BoxStream;
+
+/// Get count of guild settings
+pub(crate) async fn count_settings(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
+    Ok(sqlx::query!("select count(*) as \"i64!\" from settings")
+        .fetch_one(pool)
+        .await?
+        .i64)
+}
+
+/// Stream all guild settings
+pub(crate) fn stream_settings(
+    pool: &sqlx::SqlitePool,
+) -> BoxStream<'_, Result<Setting, sqlx::Error>> {
+    sqlx::query_as!(Setting, "select * from settings order by key").fetch(pool)
+}
+
+/// Get all guild settings overriding environment defaults
+pub(crate) async fn get_all_settings(pool: &sqlx::SqlitePool) -> Result<Vec<Setting>, Error> {
+    Ok(
+        sqlx::query_as!(Setting, "select * from settings order by key")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+/// Set guild setting, replacing any previous value
+pub(crate) async fn set_setting(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    key: &str,
+    value: i64,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    let before = sqlx::query_as!(Setting, "select * from settings where key=$1", key)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let after = sqlx::query_as!(
+        Setting,
+        "insert into settings (key, value) values ($1, $2)
+        on conflict (key) do update set value=excluded.value, updated_at=unixepoch()
+        returning *",
+        key,
+        value
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    insert_audit(
+        &mut tx,
+        actor,
+        "set_setting",
+        None,
+        json(&before),
+        json(&after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete guild setting, returning to the environment default
+pub(crate) async fn delete_setting(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    key: &str,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let Some(s) = sqlx::query_as!(
+        Setting,
+        "delete from settings where key=$1 returning *",
+        key
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(false);
+    };
+    insert_audit(&mut tx, actor, "delete_setting", None, json(&s), None).await?;
+    tx.commit().await?;
+    Ok(true)
+}
@@ -0,0 +1,55 @@
+use crate::Error;
+
+/// Store nonce of a new login state token, returning its expiry, and prune expired tokens
+pub(crate) async fn insert_login_state(
+    pool: &sqlx::SqlitePool,
+    nonce: &str,
+    id: i64,
+    ttl: i64,
+) -> Result<i64, Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!("delete from login_states where expires_at < unixepoch()")
+        .execute(&mut *tx)
+        .await?;
+    let expires_at = sqlx::query!(
+        "insert into login_states (nonce, discord_id, expires_at) \
+            values ($1, $2, unixepoch() + $3) returning expires_at",
+        nonce,
+        id,
+        ttl
+    )
+    .fetch_one(&mut *tx)
+    .await?
+    .expires_at;
+    tx.commit().await?;
+    Ok(expires_at)
+}
+
+/// Mark login state token as used
+///
+/// Returns `None` if the token is unknown, or whether it was unused before this call
+pub(crate) async fn use_login_state(
+    pool: &sqlx::SqlitePool,
+    nonce: &str,
+    id: i64,
+) -> Result<Option<bool>, Error> {
+    let used = sqlx::query!(
+        "update login_states set used_at=unixepoch() \
+            where nonce=$1 and discord_id=$2 and used_at is null returning nonce",
+        nonce,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+    if used.is_some() {
+        return Ok(Some(true));
+    }
+    let known = sqlx::query!(
+        "select nonce from login_states where nonce=$1 and discord_id=$2",
+        nonce,
+        id
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(known.map(|_| false))
+}
@@ -0,0 +1,97 @@
+use crate::db::{insert_audit, json};
+use crate::{Actor, Error, SavedTemplate};
+use futures::stream::BoxStream;
+
+/// Get count of edited message templates
+pub(crate) async fn count_templates(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
+    Ok(sqlx::query!("select count(*) as \"i64!\" from templates")
+        .fetch_one(pool)
+        .await?
+        .i64)
+}
+
+/// Stream all edited message templates
+pub(crate) fn stream_templates(
+    pool: &sqlx::SqlitePool,
+) -> BoxStream<'_, Result<SavedTemplate, sqlx::Error>> {
+    sqlx::query_as!(SavedTemplate, "select * from templates order by key").fetch(pool)
+}
+
+/// Get all edited message templates
+pub(crate) async fn get_all_templates(
+    pool: &sqlx::SqlitePool,
+) -> Result<Vec<SavedTemplate>, Error> {
+    Ok(
+        sqlx::query_as!(SavedTemplate, "select * from templates order by key")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+/// Get edited message template by key
+pub(crate) async fn get_template(
+    pool: &sqlx::SqlitePool,
+    key: &str,
+) -> Result<Option<SavedTemplate>, Error> {
+    Ok(
+        sqlx::query_as!(SavedTemplate, "select * from templates where key=$1", key)
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// Set message template, replacing any previous edit
+pub(crate) async fn set_template(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    key: &str,
+    body: &str,
+) -> Result<(), Error> {
+    let mut tx = pool.begin().await?;
+    let before = sqlx::query_as!(SavedTemplate, "select * from templates where key=$1", key)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let after = sqlx::query_as!(
+        SavedTemplate,
+        "insert into templates (key, body) values ($1, $2)
+        on conflict (key) do update set body=excluded.body, updated_at=unixepoch()
+        returning *",
+        key,
+        body
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    insert_audit(
+        &mut tx,
+        actor,
+        "set_template",
+        None,
+        json(&before),
+        json(&after),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Delete edited message template, returning to the default text
+pub(crate) async fn delete_template(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    key: &str,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let Some(t) = sqlx::query_as!(
+        SavedTemplate,
+        "delete from templates where key=$1 returning *",
+        key
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(false);
+    };
+    insert_audit(&mut tx, actor, "delete_template", None, json(&t), None).await?;
+    tx.commit().await?;
+    Ok(true)
+}
@@ -0,0 +1,204 @@
+use crate::db::{insert_audit, json};
+use crate::{webhooks::Event, Actor, Error, WebhookDelivery};
+
+/// Record event as part of the transaction making the change, so it is only sent if the
+/// change is committed, pruning events that were never dispatched after 7 days
+pub(crate) async fn insert_webhook_event(
+    conn: &mut sqlx::SqliteConnection,
+    event: &Event,
+) -> Result<(), Error> {
+    sqlx::query!("delete from webhook_events where created_at < unixepoch('now', '-7 days')")
+        .execute(&mut *conn)
+        .await?;
+    let (name, payload) = (event.name(), event.payload()?);
+    sqlx::query!(
+        "insert into webhook_events (event, payload) values ($1, $2)",
+        name,
+        payload
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Move recorded events to the delivery queue of each endpoint, and prune old delivered
+/// events
+///
+/// Returns the number of events dispatched
+pub(crate) async fn dispatch_webhook_events(
+    pool: &sqlx::SqlitePool,
+    endpoints: &[String],
+) -> Result<usize, Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        "delete from webhook_deliveries \
+            where status='delivered' and delivered_at < unixepoch('now', '-7 days')"
+    )
+    .execute(&mut *tx)
+    .await?;
+    let events = sqlx::query!("delete from webhook_events returning id, event, payload")
+        .fetch_all(&mut *tx)
+        .await?;
+    let mut events = events
+        .into_iter()
+        .map(|e| (e.id, e.event, e.payload))
+        .collect::<Vec<_>>();
+    events.sort_by_key(|(id, ..)| *id);
+    for (_, event, payload) in &events {
+        for endpoint in endpoints {
+            sqlx::query!(
+                "insert into webhook_deliveries (endpoint, event, payload) values ($1, $2, $3)",
+                endpoint,
+                event,
+                payload
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(events.len())
+}
+
+/// Get pending deliveries due for an attempt, oldest first
+pub(crate) async fn get_due_webhook_deliveries(
+    pool: &sqlx::SqlitePool,
+    limit: i64,
+) -> Result<Vec<WebhookDelivery>, Error> {
+    Ok(sqlx::query_as!(
+        WebhookDelivery,
+        "select id as \"id!\", endpoint, event, payload, status, attempts, next_attempt_at, \
+            last_error, created_at, delivered_at from webhook_deliveries \
+            where status='pending' and next_attempt_at <= unixepoch() \
+            order by id limit $1",
+        limit
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Get failed deliveries, newest first
+pub(crate) async fn get_failed_webhook_deliveries(
+    pool: &sqlx::SqlitePool,
+    limit: i64,
+) -> Result<Vec<WebhookDelivery>, Error> {
+    Ok(sqlx::query_as!(
+        WebhookDelivery,
+        "select * from webhook_deliveries where status='failed' order by id desc limit $1",
+        limit
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Mark delivery as successfully delivered
+pub(crate) async fn set_webhook_delivered(pool: &sqlx::SqlitePool, id: i64) -> Result<(), Error> {
+    sqlx::query!(
+        "update webhook_deliveries \
+            set status='delivered', attempts=attempts + 1, delivered_at=unixepoch(), last_error=null \
+            where id=$1",
+        id
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Record failed attempt, retrying after `retry_in` seconds or giving up if `None`
+pub(crate) async fn set_webhook_attempt_failed(
+    pool: &sqlx::SqlitePool,
+    id: i64,
+    error: &str,
+    retry_in: Option<i64>,
+) -> Result<(), Error> {
+    sqlx::query!(
+        "update webhook_deliveries \
+            set status=iif($3 is null, 'failed', 'pending'), attempts=attempts + 1, \
+            next_attempt_at=unixepoch() + coalesce($3, 0), last_error=$2 \
+            where id=$1",
+        id,
+        error,
+        retry_in
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Queue failed deliveries for another round of attempts, all of them if no ID is given
+///
+/// Returns the number of deliveries queued
+pub(crate) async fn replay_webhook_deliveries(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: Option<i64>,
+) -> Result<u64, Error> {
+    let mut tx = pool.begin().await?;
+    let ids = sqlx::query!(
+        "update webhook_deliveries \
+            set status='pending', attempts=0, next_attempt_at=unixepoch() \
+            where status='failed' and ($1 is null or id=$1) returning id",
+        id
+    )
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|r| r.id)
+    .collect::<Vec<_>>();
+    if !ids.is_empty() {
+        insert_audit(&mut tx, actor, "replay_webhooks", None, None, json(&ids)).await?;
+    }
+    tx.commit().await?;
+    Ok(ids.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, test_pool};
+    use crate::{Fresher, Method, PendingMember};
+
+    #[tokio::test]
+    async fn events_are_recorded_with_their_change() {
+        let pool = test_pool().await;
+        let pending = PendingMember {
+            discord_id: 1,
+            shortcode: "ab123".to_string(),
+            realname: "Real Name".to_string(),
+        };
+        db::insert_pending(&pool, Actor::System, pending)
+            .await
+            .unwrap();
+        let missing = db::insert_member_from_pending(
+            &pool,
+            Actor::System,
+            2,
+            "Nick",
+            Fresher::No,
+            Method::Login,
+            None,
+        );
+        assert!(missing.await.is_err());
+        db::insert_member_from_pending(
+            &pool,
+            Actor::System,
+            1,
+            "Nick",
+            Fresher::No,
+            Method::Login,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let endpoints = [
+            "https://a.example".to_string(),
+            "https://b.example".to_string(),
+        ];
+        assert_eq!(dispatch_webhook_events(&pool, &endpoints).await.unwrap(), 1);
+        let due = get_due_webhook_deliveries(&pool, 10).await.unwrap();
+        assert_eq!(due.len(), 2);
+        assert!(due.iter().all(|d| d.event == "verified"));
+        assert_eq!(dispatch_webhook_events(&pool, &endpoints).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::db::{insert_audit, json};
+use crate::{Actor, Error, Fresher, Member, MembershipYear};
+use futures::stream::BoxStream;
+
+/// Get count of entries in membership years table
+pub(crate) async fn count_membership_years(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
+    Ok(
+        sqlx::query!("select count(*) as \"i64!\" from membership_years")
+            .fetch_one(pool)
+            .await?
+            .i64,
+    )
+}
+
+/// Stream all entries in membership years table
+pub(crate) fn stream_membership_years(
+    pool: &sqlx::SqlitePool,
+) -> BoxStream<'_, Result<MembershipYear, sqlx::Error>> {
+    sqlx::query_as!(
+        MembershipYear,
+        "select * from membership_years order by discord_id, year"
+    )
+    .fetch(pool)
+}
+
+/// Get start year of the current academic year, which begins in August
+pub(crate) async fn current_academic_year(pool: &sqlx::SqlitePool) -> Result<i64, Error> {
+    Ok(
+        sqlx::query!(
+            "select cast(strftime('%Y', 'now', '-7 months') as integer) as \"year!: i64\""
+        )
+        .fetch_one(pool)
+        .await?
+        .year,
+    )
+}
+
+/// Record membership for the current academic year, as part of an existing transaction
+pub(crate) async fn insert_membership_year(
+    conn: &mut sqlx::SqliteConnection,
+    id: i64,
+    fresher: Fresher,
+) -> Result<(), Error> {
+    sqlx::query!(
+        "insert or ignore into membership_years \
+            values ($1, cast(strftime('%Y', 'now', '-7 months') as integer), $2)",
+        id,
+        fresher
+    )
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+/// Get all academic years recorded for a member
+pub(crate) async fn get_membership_years_by_id(
+    pool: &sqlx::SqlitePool,
+    id: i64,
+) -> Result<Vec<MembershipYear>, Error> {
+    Ok(sqlx::query_as!(
+        MembershipYear,
+        "select * from membership_years where discord_id=$1 order by year",
+        id
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Renew member for the current academic year, returning false if not a member
+///
+/// A renewal is never a fresher year, so it doesn't stop last year's freshers from being
+/// archived at rollover
+pub(crate) async fn renew_member(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    id: i64,
+) -> Result<bool, Error> {
+    let mut tx = pool.begin().await?;
+    let Some(m) = sqlx::query_as!(Member, "select * from members where discord_id=$1", id)
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        return Ok(false);
+    };
+    insert_membership_year(&mut tx, id, Fresher::No).await?;
+    insert_audit(&mut tx, actor, "renew_member", Some(id), None, json(&m)).await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Get members not verified or renewed for the given academic year
+pub(crate) async fn get_lapsed_members(
+    pool: &sqlx::SqlitePool,
+    year: i64,
+) -> Result<Vec<Member>, Error> {
+    Ok(sqlx::query_as!(
+        Member,
+        "select * from members where discord_id not in \
+            (select discord_id from membership_years where year=$1)",
+        year
+    )
+    .fetch_all(pool)
+    .await?)
+}
+
+/// Get freshers not verified as freshers in the given academic year
+pub(crate) async fn get_expired_freshers(
+    pool: &sqlx::SqlitePool,
+    year: i64,
+) -> Result<Vec<Member>, Error> {
+    expired_freshers(&mut *pool.acquire().await?, year).await
+}
+
+/// Get freshers not verified as freshers in the given academic year, as part of an
+/// existing transaction
+async fn expired_freshers(
+    conn: &mut sqlx::SqliteConnection,
+    year: i64,
+) -> Result<Vec<Member>, Error> {
+    Ok(sqlx::query_as!(
+        Member,
+        "select * from members where fresher!='no' and discord_id not in \
+            (select discord_id from membership_years where year=$1 and fresher!='no')",
+        year
+    )
+    .fetch_all(conn)
+    .await?)
+}
+
+/// Archive freshers from previous academic years by setting them to non-freshers, their
+/// membership years are left as recorded at verification
+pub(crate) async fn archive_expired_freshers(
+    pool: &sqlx::SqlitePool,
+    actor: Actor<'_>,
+    year: i64,
+) -> Result<Vec<Member>, Error> {
+    let mut tx = pool.begin().await?;
+    let freshers = expired_freshers(&mut tx, year).await?;
+    for m in &freshers {
+        let (id, before) = (m.discord_id, json(m));
+        let after = sqlx::query_as!(
+            Member,
+            "update members set fresher='no' where discord_id=$1 returning *",
+            id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        insert_audit(
+            &mut tx,
+            actor,
+            "archive_fresher",
+            Some(id),
+            before,
+            json(&after),
+        )
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(freshers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{import_member, import_membership_year, test_pool};
+    use crate::Method;
+
+    #[tokio::test]
+    async fn renewed_fresher_is_archived_at_rollover() {
+        let pool = test_pool().await;
+        let year = current_academic_year(&pool).await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        let member = Member {
+            discord_id: 1,
+            shortcode: "ab123".to_string(),
+            nickname: "Nick".to_string(),
+            realname: "Real Name".to_string(),
+            fresher: Fresher::YesUg,
+            verified_at: None,
+            method: Method::Login,
+            verified_by: None,
+        };
+        import_member(&mut conn, Actor::System, member, false)
+            .await
+            .unwrap();
+        let verified = MembershipYear {
+            discord_id: 1,
+            year: year - 1,
+            fresher: Fresher::YesUg,
+        };
+        import_membership_year(&mut conn, Actor::System, verified, false)
+            .await
+            .unwrap();
+        drop(conn);
+
+        // Renewing (by command or EA sync) before rollover doesn't make them a fresher again
+        assert!(renew_member(&pool, Actor::System, 1).await.unwrap());
+        let expired = get_expired_freshers(&pool, year).await.unwrap();
+        assert_eq!(expired.len(), 1);
+
+        let archived = archive_expired_freshers(&pool, Actor::System, year)
+            .await
+            .unwrap();
+        assert_eq!(archived.len(), 1);
+        assert!(get_expired_freshers(&pool, year).await.unwrap().is_empty());
+        assert!(get_lapsed_members(&pool, year).await.unwrap().is_empty());
+        let years = get_membership_years_by_id(&pool, 1).await.unwrap();
+        let years = years
+            .iter()
+            .map(|y| (y.year, y.fresher.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            years,
+            [
+                (year - 1, Fresher::YesUg.to_string()),
+                (year, Fresher::No.to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{
+    config::{self, Key, Kind},
+    db, Data,
+};
+use poise::{
+    serenity_prelude::{self as serenity, CreateEmbed, Permissions},
+    ChoiceParameter as _,
+};
+use std::fmt::Write as _;
+
+/// Permissions needed in the added users and general channels
+const POST: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES)
+    .union(Permissions::EMBED_LINKS);
+
+/// Result of a single diagnostic check
+struct Check {
+    name: String,
+    /// Reason the check failed, if it did
+    problem: Option<String>,
+    /// Whether failing this check breaks verification
+    fatal: bool,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, result: Result<(), String>, fatal: bool) -> Self {
+        Self {
+            name: name.into(),
+            problem: result.err(),
+            fatal,
+        }
+    }
+}
+
+/// Checklist of the configured server, roles and channels, the EA API and the fuzzy
+/// extension
+pub(crate) struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn run(http: &serenity::Http, data: &Data) -> Self {
+        let mut checks = vec![Check::new(
+            "Fuzzy SQLite extension",
+            db::ping_fuzzy(&data.db).await.map_err(|e| e.to_string()),
+            true,
+        )];
+        checks.push(Check::new(
+            "EA API",
+            data.ea
+                .members(true)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            false,
+        ));
+        checks.extend(guild_checks(http, data, data.guild()).await);
+        Self { checks }
+    }
+
+    /// Whether any check failed that breaks verification
+    pub(crate) fn fatal(&self) -> bool {
+        self.checks.iter().any(|c| c.fatal && c.problem.is_some())
+    }
+
+    /// Log failed checks, with fatal misconfigurations as errors
+    pub(crate) fn log(&self) {
+        for c in &self.checks {
+            match &c.problem {
+                Some(problem) if c.fatal => {
+                    tracing::error!("MISCONFIGURED: {}: {problem}", c.name);
+                }
+                Some(problem) => tracing::warn!("{}: {problem}", c.name),
+                None => {}
+            }
+        }
+        let failed = self.checks.iter().filter(|c| c.problem.is_some()).count();
+        if failed == 0 {
+            tracing::info!("All {} diagnostic checks passed", self.checks.len());
+        } else {
+            tracing::warn!("{failed}/{} diagnostic checks failed", self.checks.len());
+        }
+    }
+
+    pub(crate) fn embed(&self) -> CreateEmbed {
+        let mut list = String::new();
+        for c in &self.checks {
+            match &c.problem {
+                None => writeln!(list, "✅ {}", c.name),
+                Some(problem) if c.fatal => writeln!(list, "❌ {}: {problem}", c.name),
+                Some(problem) => writeln!(list, "⚠️ {}: {problem}", c.name),
+            }
+            .expect("String write! is infallible");
+        }
+        let passed = self.checks.iter().filter(|c| c.problem.is_none()).count();
+        CreateEmbed::new()
+            .title("Diagnostics")
+            .description(list)
+            .field("Passed", format!("{passed}/{}", self.checks.len()), true)
+            .timestamp(serenity::Timestamp::now())
+    }
+}
+
+/// Check configured roles and channels exist in the server, and that Nano can manage
+/// each role and post in each channel
+async fn guild_checks(http: &serenity::Http, data: &Data, guild: config::Guild) -> Vec<Check> {
+    let server = match guild.server.to_partial_guild(http).await {
+        Ok(server) => server,
+        Err(e) => return vec![Check::new("Server", Err(e.to_string()), true)],
+    };
+    let bot = match http.get_current_user().await {
+        Ok(user) => server.member(http, user.id).await,
+        Err(e) => Err(e),
+    };
+    let bot = match bot {
+        Ok(bot) => bot,
+        Err(e) => {
+            let problem = format!("Nano is not a member of {}: {e}", server.name);
+            return vec![Check::new("Server", Err(problem), true)];
+        }
+    };
+    let channels = match server.channels(http).await {
+        Ok(channels) => channels,
+        Err(e) => return vec![Check::new("Server channels", Err(e.to_string()), true)],
+    };
+    let mut checks = vec![Check::new(format!("Server {}", server.name), Ok(()), true)];
+
+    let permissions = server.member_permissions(&bot);
+    let top = bot
+        .roles
+        .iter()
+        .filter_map(|r| server.roles.get(r))
+        .map(|r| r.position)
+        .max()
+        .unwrap_or_default();
+    for key in Key::ALL {
+        let id = guild.get(key);
+        let result = match key.kind() {
+            Kind::Server => continue,
+            Kind::Role => match server.roles.get(&id.into()) {
+                None => Err("not found in server".to_string()),
+                Some(_) if !permissions.manage_roles() => {
+                    Err("Nano lacks the Manage Roles permission".to_string())
+                }
+                Some(role) if role.position >= top => {
+                    Err(format!("Nano's highest role is not above @{}", role.name))
+                }
+                Some(_) => Ok(()),
+            },
+            Kind::Channel => match channels.get(&id.into()) {
+                None => Err("not found in server".to_string()),
+                Some(channel) if !server.user_permissions_in(channel, &bot).contains(POST) => {
+                    Err(format!("Nano cannot post embeds in #{}", channel.name))
+                }
+                Some(_) => Ok(()),
+            },
+        };
+        checks.push(Check::new(key.name(), result, true));
+    }
+
+    let staff = [
+        ("Committee role", Some(data.committee)),
+        ("Reviewer role", data.reviewer),
+    ];
+    for (name, role) in staff {
+        if let Some(role) = role {
+            let result = server
+                .roles
+                .contains_key(&role)
+                .then_some(())
+                .ok_or_else(|| "not found in server".to_string());
+            checks.push(Check::new(name, result, true));
+        }
+    }
+    checks
+}
@@ -1,4 +1,15 @@
-#[derive(serde::Deserialize)]
+use crate::{metrics, var, Error};
+use anyhow::Context as _;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{sync::Mutex, time::Instant};
+
+const ATTEMPTS: u32 = 3;
+const BACKOFF: Duration = Duration::from_millis(500);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const MIN_REFRESH: Duration = Duration::from_secs(30);
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Clone, serde::Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub(crate) struct Member {
     pub first_name: String,
@@ -9,17 +20,244 @@ pub(crate) struct Member {
     pub order_no: usize,
 }
 
-#[tracing::instrument(skip_all)]
-pub(crate) async fn get_members_list(
-    api_key: &str,
-    url: &str,
-) -> Result<Vec<Member>, reqwest::Error> {
-    let members = reqwest::Client::new()
-        .get(url)
-        .header("X-API-Key", api_key)
-        .send()
-        .await?
-        .json::<Vec<Member>>()
-        .await?;
-    Ok(members)
+impl Member {
+    /// Shortcode used for verification, CID if member has no Imperial login
+    pub(crate) fn key(&self) -> String {
+        if self.login.is_empty() {
+            self.cid.to_lowercase()
+        } else {
+            self.login.to_lowercase()
+        }
+    }
+}
+
+/// Membership list as last downloaded, indexed by (order number, shortcode)
+pub(crate) struct Members {
+    fetched: Instant,
+    pub list: Vec<Member>,
+    index: HashMap<(usize, String), usize>,
+}
+
+impl Members {
+    fn new(list: Vec<Member>) -> Self {
+        let index = list
+            .iter()
+            .enumerate()
+            .map(|(i, m)| ((m.order_no, m.key()), i))
+            .collect();
+        Self {
+            fetched: Instant::now(),
+            list,
+            index,
+        }
+    }
+
+    fn find(&self, order: usize, shortcode: &str) -> Option<&Member> {
+        self.index
+            .get(&(order, shortcode.to_lowercase()))
+            .map(|&i| &self.list[i])
+    }
+}
+
+/// Result of the last attempt to download the membership list
+#[derive(Clone, serde::Serialize)]
+pub(crate) struct Fetch {
+    /// Unix timestamp of the attempt
+    pub at: i64,
+    pub error: Option<String>,
+}
+
+/// Shared EA API client, caching the membership list for `ttl`
+pub(crate) struct Client {
+    http: reqwest::Client,
+    api_key: String,
+    url: String,
+    ttl: Duration,
+    cache: std::sync::Mutex<Option<Arc<Members>>>,
+    /// Held while downloading, so concurrent callers share one download
+    fetching: Mutex<()>,
+    last_fetch: std::sync::Mutex<Option<Fetch>>,
+}
+
+impl Client {
+    pub(crate) fn new(api_key: String, url: String, ttl: Duration) -> Result<Self, reqwest::Error> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(TIMEOUT)
+            .build()?;
+        Ok(Self {
+            http,
+            api_key,
+            url,
+            ttl,
+            cache: std::sync::Mutex::new(None),
+            fetching: Mutex::new(()),
+            last_fetch: std::sync::Mutex::new(None),
+        })
+    }
+
+    pub(crate) fn from_env() -> Result<Self, Error> {
+        let ttl = std::env::var("EA_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(300);
+        Ok(Self::new(
+            var!("EA_API_KEY"),
+            var!("EA_API_URL"),
+            Duration::from_secs(ttl),
+        )?)
+    }
+
+    /// Result of the last download, `None` if the list has not been needed yet
+    pub(crate) fn last_fetch(&self) -> Option<Fetch> {
+        self.last_fetch
+            .lock()
+            .expect("last fetch lock is never poisoned")
+            .clone()
+    }
+
+    /// Last downloaded membership list, however old
+    fn cached(&self) -> Option<Arc<Members>> {
+        self.cache
+            .lock()
+            .expect("cache lock is never poisoned")
+            .clone()
+    }
+
+    /// Cached membership list, unless expired or `refresh` is set
+    ///
+    /// Forced refreshes are skipped if the cache was updated very recently
+    fn fresh(&self, refresh: bool) -> Option<Arc<Members>> {
+        self.cached().filter(|members| {
+            let age = members.fetched.elapsed();
+            age < MIN_REFRESH || (!refresh && age < self.ttl)
+        })
+    }
+
+    /// Get membership list, from cache unless expired or `refresh` is set
+    ///
+    /// Without `refresh`, the expired list is served if it can't be downloaded, or while
+    /// another caller is downloading it, so an EA outage doesn't block lookups
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn members(&self, refresh: bool) -> Result<Arc<Members>, reqwest::Error> {
+        if let Some(members) = self.fresh(refresh) {
+            return Ok(members);
+        }
+        let stale = self.cached().filter(|_| !refresh);
+        let _fetching = match (self.fetching.try_lock(), &stale) {
+            (Ok(guard), _) => guard,
+            (Err(_), Some(members)) => return Ok(members.clone()),
+            (Err(_), None) => {
+                let guard = self.fetching.lock().await;
+                // The download we waited for has probably filled the cache
+                if let Some(members) = self.fresh(refresh) {
+                    return Ok(members);
+                }
+                guard
+            }
+        };
+        let result = self.get_members_list().await;
+        *self
+            .last_fetch
+            .lock()
+            .expect("last fetch lock is never poisoned") = Some(Fetch {
+            at: poise::serenity_prelude::Timestamp::now().unix_timestamp(),
+            error: result.as_ref().err().map(ToString::to_string),
+        });
+        match (result, stale) {
+            (Ok(list), _) => {
+                let members = Arc::new(Members::new(list));
+                *self.cache.lock().expect("cache lock is never poisoned") = Some(members.clone());
+                Ok(members)
+            }
+            (Err(e), Some(members)) => {
+                tracing::warn!(
+                    "EA refresh failed, serving list from {:?} ago: {e}",
+                    members.fetched.elapsed()
+                );
+                Ok(members)
+            }
+            (Err(e), None) => Err(e),
+        }
+    }
+
+    /// Find member by order number and shortcode (or CID), refreshing the cache once on a miss
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn find(
+        &self,
+        order: &str,
+        shortcode: &str,
+    ) -> Result<Option<Member>, reqwest::Error> {
+        let Ok(order) = order.trim().parse::<usize>() else {
+            return Ok(None);
+        };
+        let shortcode = shortcode.trim();
+        if let Some(m) = self.members(false).await?.find(order, shortcode) {
+            return Ok(Some(m.clone()));
+        }
+        Ok(self.members(true).await?.find(order, shortcode).cloned())
+    }
+
+    /// Download membership list, retrying with exponential backoff
+    async fn get_members_list(&self) -> Result<Vec<Member>, reqwest::Error> {
+        let mut attempt = 0;
+        loop {
+            let timer = metrics::EA_LATENCY.start_timer();
+            let result = async {
+                self.http
+                    .get(&self.url)
+                    .header("X-API-Key", &self.api_key)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<Vec<Member>>()
+                    .await
+            }
+            .await;
+            timer.observe_duration();
+            if result.is_err() {
+                metrics::EA_ERRORS.inc();
+            }
+            attempt += 1;
+            match result {
+                Ok(members) => return Ok(members),
+                Err(e) if attempt < ATTEMPTS => {
+                    tracing::warn!("EA request failed (attempt {attempt}): {e}");
+                    tokio::time::sleep(BACKOFF * 2u32.pow(attempt - 1)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn expired_list_is_served_when_refresh_fails() {
+        // Nothing listens on the port, so every download fails
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/members", listener.local_addr().unwrap());
+        drop(listener);
+        let client = Client::new("key".to_string(), url, Duration::from_mins(5)).unwrap();
+        assert!(client.members(false).await.is_err());
+
+        let member = Member {
+            first_name: "First".to_string(),
+            surname: "Last".to_string(),
+            cid: "00000001".to_string(),
+            login: "ab123".to_string(),
+            order_no: 1,
+        };
+        let mut members = Members::new(vec![member]);
+        members.fetched -= Duration::from_mins(10);
+        *client.cache.lock().unwrap() = Some(Arc::new(members));
+
+        let found = client.find("1", "AB123").await.unwrap();
+        assert_eq!(found.map(|m| m.cid), Some("00000001".to_string()));
+        assert!(client.last_fetch().unwrap().error.is_some());
+        assert!(client.members(true).await.is_err());
+    }
 }
@@ -0,0 +1,450 @@
+use poise::ChoiceParameter;
+
+/// Language of user-facing text, picked from the interaction locale
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ChoiceParameter)]
+pub(crate) enum Locale {
+    #[default]
+    #[name = "English"]
+    En,
+    #[name = "简体中文"]
+    ZhCn,
+}
+
+impl Locale {
+    pub(crate) const ALL: [Locale; 2] = [Locale::En, Locale::ZhCn];
+
+    /// Pick locale from a Discord locale such as `zh-CN`, defaulting to English
+    pub(crate) fn from_discord(locale: &str) -> Self {
+        match locale {
+            "zh-CN" => Locale::ZhCn,
+            _ => Locale::En,
+        }
+    }
+
+    /// Discord locale code
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en-GB",
+            Locale::ZhCn => "zh-CN",
+        }
+    }
+
+    fn table(self) -> &'static [(Msg, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::ZhCn => ZH_CN,
+        }
+    }
+
+    /// Text of message in locale, falling back to English if it is missing
+    pub(crate) fn t(self, msg: Msg) -> &'static str {
+        let find = |table: &'static [(Msg, &'static str)]| {
+            table.iter().find(|(m, _)| *m == msg).map(|(_, text)| *text)
+        };
+        find(self.table()).or_else(|| find(EN)).unwrap_or_default()
+    }
+}
+
+macro_rules! messages {
+    ($($msg: ident),* $(,)?) => {
+        /// Key of a localised message
+        #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+        pub(crate) enum Msg {
+            $($msg),*
+        }
+
+        #[cfg(test)]
+        impl Msg {
+            pub(crate) const ALL: &[Msg] = &[$(Msg::$msg),*];
+        }
+    };
+}
+
+messages! {
+    InfoTemplate,
+    StartTemplate,
+    LoginIntroTemplate,
+    LoginFormTemplate,
+    MembershipIntroTemplate,
+    ManualIntroTemplate,
+    WelcomeTemplate,
+    WelcomeFresherTemplate,
+    SomethingWrong,
+    SomethingWrongContact,
+    Unknown,
+    LockedOut,
+    AlreadyVerified,
+    LoginButton,
+    MembershipButton,
+    ManualButton,
+    LoginHereButton,
+    ThenContinueButton,
+    FormButton,
+    FresherButton,
+    NonFresherButton,
+    PgFresherButton,
+    NameButton,
+    LoginIncomplete,
+    AreYouFresher,
+    NicknamePrompt,
+    NicknameTitle,
+    NicknameLabel,
+    NamePlaceholder,
+    MembershipTitle,
+    OrderLabel,
+    ShortcodeLabel,
+    ManualTitle,
+    RealnameLabel,
+    UrlLabel,
+    UrlPlaceholder,
+    Verified,
+    VerifiedFresher,
+    EaUnavailable,
+    OrderNotFound,
+    OrderClaimed,
+    InvalidUrl,
+    RequestSent,
+    RequestSentIssue,
+    RequestFailed,
+}
+
+const EN: &[(Msg, &str)] = &[
+    (
+        Msg::InfoTemplate,
+        indoc::indoc! {"
+            Nano is a Discord bot written with serenity-rs/poise and tokio-rs/axum.
+
+            It allows members and Imperial students to automatically verify themselves and gain access to the ICAS Discord server.
+
+            If you have any questions, feel free to ping or message {contact}
+        "},
+    ),
+    (
+        Msg::StartTemplate,
+        indoc::indoc! {"
+            There are 3 available methods for verification.
+            - 🚀 Automatic verification via Imperial Login (Quickest)
+            - ✈️ Automatic verification via ICAS Membership (Easiest)
+            - 🚗 Manual verification, eg. using College ID Card or Acceptance Letter
+        "},
+    ),
+    (
+        Msg::LoginIntroTemplate,
+        indoc::indoc! {"
+            To use automatic verification via Imperial Login:
+            - Open the link provided and login using your shortcode
+            - Your account will be checked and then the login details immediately discarded
+            - Your shortcode will then be connected to your Discord Account by Nano
+
+            You can then complete the remaining details in the next step!
+        "},
+    ),
+    (
+        Msg::LoginFormTemplate,
+        indoc::indoc! {"
+            Congratulations, your Imperial shortcode has been connected to your Discord Account by Nano!
+
+            The last step is a short form with some extra details
+        "},
+    ),
+    (
+        Msg::MembershipIntroTemplate,
+        indoc::indoc! {"
+            To use automatic verification via Membership:
+            - Enter your Union order number (from this academic year)
+            - Enter your Imperial shortcode
+              - For Life members, your shortcode will be from when you were a student
+              - For Associate members, this is your CID, in the format `AM-12345` or similar
+            - Enter your preferred name for Nano whois commands
+            - Your shortcode will then be connected to your Discord Account by Nano
+
+            First, are you a fresher?
+        "},
+    ),
+    (
+        Msg::ManualIntroTemplate,
+        indoc::indoc! {"
+            Submit details to be manually checked by a committee member:
+            - Your Imperial Shortcode
+            - Your First and Last Names as on your Imperial record
+            - Preferred First and Last Names for the Nano whois command
+            - URL to proof of being an Imperial student, e.g. photo of College ID Card \
+                or screenshot of College Acceptance Letter, if you need to upload this, \
+                you can send it in a DM and then copy the image URL
+
+            We try to respond quickly but this may take a day or two during busy term times :)
+
+            First, are you a fresher? (And if yes, a postgraduate fresher?)
+        "},
+    ),
+    (
+        Msg::WelcomeTemplate,
+        "Welcome to ICAS {user}, if you have any questions, feel free to ping a committee member!",
+    ),
+    (
+        Msg::WelcomeFresherTemplate,
+        "Welcome to ICAS {user}, if you have any questions, \
+        feel free to ping a committee member, and look out for other {fresher} in green!",
+    ),
+    (
+        Msg::SomethingWrong,
+        "Sorry, something went wrong. Please try again",
+    ),
+    (
+        Msg::SomethingWrongContact,
+        "Sorry, something went wrong. Please try again, or contact an Admin if it happens again.",
+    ),
+    (
+        Msg::Unknown,
+        "Sorry, something went wrong. Please try again or message {contact} for help",
+    ),
+    (
+        Msg::LockedOut,
+        "Sorry, there have been too many attempts from your account. \
+        Please try again {until} or contact an Admin",
+    ),
+    (
+        Msg::AlreadyVerified,
+        "Welcome, you're already verified, re-applied your roles!",
+    ),
+    (Msg::LoginButton, "Login"),
+    (Msg::MembershipButton, "Membership"),
+    (Msg::ManualButton, "Manual"),
+    (Msg::LoginHereButton, "Login Here"),
+    (Msg::ThenContinueButton, "Then continue"),
+    (Msg::FormButton, "Form"),
+    (Msg::FresherButton, "Fresher"),
+    (Msg::NonFresherButton, "Non-fresher"),
+    (Msg::PgFresherButton, "Postgraduate fresher"),
+    (Msg::NameButton, "Name"),
+    (
+        Msg::LoginIncomplete,
+        "Error, have you completed login verification via the link?",
+    ),
+    (Msg::AreYouFresher, "Are you a fresher?"),
+    (
+        Msg::NicknamePrompt,
+        "And a preferred name for Nano whois commands",
+    ),
+    (Msg::NicknameTitle, "Preferred Name"),
+    (Msg::NicknameLabel, "Preferred name for Nano whois commands"),
+    (Msg::NamePlaceholder, "Firstname Lastname"),
+    (Msg::MembershipTitle, "ICAS Membership Verification"),
+    (Msg::OrderLabel, "ICAS Membership Union Order Number"),
+    (Msg::ShortcodeLabel, "Imperial Shortcode"),
+    (Msg::ManualTitle, "Manual Verification"),
+    (Msg::RealnameLabel, "Name as on Imperial record"),
+    (Msg::UrlLabel, "URL to proof image"),
+    (
+        Msg::UrlPlaceholder,
+        "E.g. photo of College ID Card or screenshot of College Acceptance Letter",
+    ),
+    (
+        Msg::Verified,
+        "Congratulations, you have completed verification and now \
+        have access to the ICAS Discord",
+    ),
+    (
+        Msg::VerifiedFresher,
+        "Congratulations, you have completed verification and now \
+        have access to the ICAS Discord and freshers thread",
+    ),
+    (
+        Msg::EaUnavailable,
+        "Sorry, getting membership data failed. Please try again or contact an Admin",
+    ),
+    (
+        Msg::OrderNotFound,
+        "Sorry, your order was not found, please check the \
+        order number and that it is for your current year's membership",
+    ),
+    (
+        Msg::OrderClaimed,
+        "Sorry, this order has already been used to verify another account. \
+        Please contact an Admin if you think this is a mistake",
+    ),
+    (
+        Msg::InvalidUrl,
+        "The url provided is invalid, please try again",
+    ),
+    (
+        Msg::RequestSent,
+        "Thanks, your verification request has been sent, we'll try to get back to you quickly!",
+    ),
+    (
+        Msg::RequestSentIssue,
+        "Thanks, your verification request has been sent, but there was an issue, \
+        please ask a Committee member to take a look!",
+    ),
+    (
+        Msg::RequestFailed,
+        "Sending your verification request failed, please try again, \
+        or contact an Admin if it happens again.",
+    ),
+];
+
+const ZH_CN: &[(Msg, &str)] = &[
+    (
+        Msg::InfoTemplate,
+        indoc::indoc! {"
+            Nano 是一个使用 serenity-rs/poise 和 tokio-rs/axum 编写的 Discord 机器人。
+
+            会员和帝国理工学生可以通过它自动完成验证，并获得 ICAS Discord 服务器的访问权限。
+
+            如有任何问题，欢迎提及或私信 {contact}
+        "},
+    ),
+    (
+        Msg::StartTemplate,
+        indoc::indoc! {"
+            共有 3 种验证方式：
+            - 🚀 通过帝国理工登录自动验证（最快）
+            - ✈️ 通过 ICAS 会员资格自动验证（最简单）
+            - 🚗 人工验证，例如使用学生卡或录取通知书
+        "},
+    ),
+    (
+        Msg::LoginIntroTemplate,
+        indoc::indoc! {"
+            通过帝国理工登录自动验证：
+            - 打开提供的链接，并使用你的 shortcode 登录
+            - 你的账户会被检查，登录信息随即被丢弃
+            - 随后 Nano 会将你的 shortcode 关联到你的 Discord 账户
+
+            之后即可在下一步填写其余信息！
+        "},
+    ),
+    (
+        Msg::LoginFormTemplate,
+        indoc::indoc! {"
+            恭喜，Nano 已将你的帝国理工 shortcode 关联到你的 Discord 账户！
+
+            最后一步是填写一份简短的表格
+        "},
+    ),
+    (
+        Msg::MembershipIntroTemplate,
+        indoc::indoc! {"
+            通过会员资格自动验证：
+            - 输入你的学生会订单号（本学年）
+            - 输入你的帝国理工 shortcode
+              - 终身会员请使用你在读时的 shortcode
+              - 准会员请使用你的 CID，格式如 `AM-12345`
+            - 输入你在 Nano whois 命令中使用的名字
+            - 随后 Nano 会将你的 shortcode 关联到你的 Discord 账户
+
+            首先，你是新生吗？
+        "},
+    ),
+    (
+        Msg::ManualIntroTemplate,
+        indoc::indoc! {"
+            提交以下信息，由委员会成员人工审核：
+            - 你的帝国理工 shortcode
+            - 与帝国理工记录一致的姓名
+            - 你在 Nano whois 命令中使用的名字
+            - 帝国理工学生身份证明的链接，例如学生卡照片或录取通知书截图，\
+                如需上传，可以先在私信中发送图片，然后复制图片链接
+
+            我们会尽快回复，但在学期繁忙时可能需要一两天 :)
+
+            首先，你是新生吗？（如果是，是研究生新生吗？）
+        "},
+    ),
+    (
+        Msg::WelcomeTemplate,
+        "欢迎加入 ICAS {user}，如有任何问题，欢迎提及委员会成员！",
+    ),
+    (
+        Msg::WelcomeFresherTemplate,
+        "欢迎加入 ICAS {user}，如有任何问题，欢迎提及委员会成员，也留意一下绿色名字的其他{fresher}！",
+    ),
+    (Msg::SomethingWrong, "抱歉，出了点问题，请重试"),
+    (
+        Msg::SomethingWrongContact,
+        "抱歉，出了点问题，请重试，如果再次发生请联系管理员。",
+    ),
+    (
+        Msg::Unknown,
+        "抱歉，出了点问题，请重试或私信 {contact} 寻求帮助",
+    ),
+    (
+        Msg::LockedOut,
+        "抱歉，你的账户尝试次数过多，请于 {until} 重试或联系管理员",
+    ),
+    (
+        Msg::AlreadyVerified,
+        "欢迎，你已经完成验证，已重新为你添加身份组！",
+    ),
+    (Msg::LoginButton, "登录"),
+    (Msg::MembershipButton, "会员"),
+    (Msg::ManualButton, "人工"),
+    (Msg::LoginHereButton, "在此登录"),
+    (Msg::ThenContinueButton, "然后继续"),
+    (Msg::FormButton, "表格"),
+    (Msg::FresherButton, "新生"),
+    (Msg::NonFresherButton, "非新生"),
+    (Msg::PgFresherButton, "研究生新生"),
+    (Msg::NameButton, "名字"),
+    (Msg::LoginIncomplete, "错误，你是否已通过链接完成登录验证？"),
+    (Msg::AreYouFresher, "你是新生吗？"),
+    (Msg::NicknamePrompt, "再输入一个在 Nano whois 命令中使用的名字"),
+    (Msg::NicknameTitle, "名字"),
+    (Msg::NicknameLabel, "在 Nano whois 命令中使用的名字"),
+    (Msg::NamePlaceholder, "名 姓"),
+    (Msg::MembershipTitle, "ICAS 会员验证"),
+    (Msg::OrderLabel, "ICAS 会员学生会订单号"),
+    (Msg::ShortcodeLabel, "帝国理工 shortcode"),
+    (Msg::ManualTitle, "人工验证"),
+    (Msg::RealnameLabel, "与帝国理工记录一致的姓名"),
+    (Msg::UrlLabel, "证明图片链接"),
+    (Msg::UrlPlaceholder, "例如学生卡照片或录取通知书截图"),
+    (
+        Msg::Verified,
+        "恭喜，你已完成验证，现在可以访问 ICAS Discord",
+    ),
+    (
+        Msg::VerifiedFresher,
+        "恭喜，你已完成验证，现在可以访问 ICAS Discord 和新生频道",
+    ),
+    (
+        Msg::EaUnavailable,
+        "抱歉，获取会员数据失败，请重试或联系管理员",
+    ),
+    (
+        Msg::OrderNotFound,
+        "抱歉，未找到你的订单，请检查订单号，并确认是本学年的会员订单",
+    ),
+    (
+        Msg::OrderClaimed,
+        "抱歉，此订单已被用于验证另一个账户。如果你认为这是错误，请联系管理员",
+    ),
+    (Msg::InvalidUrl, "提供的链接无效，请重试"),
+    (
+        Msg::RequestSent,
+        "谢谢，你的验证请求已发送，我们会尽快回复你！",
+    ),
+    (
+        Msg::RequestSentIssue,
+        "谢谢，你的验证请求已发送，但出现了问题，请联系委员会成员查看！",
+    ),
+    (
+        Msg::RequestFailed,
+        "发送验证请求失败，请重试，如果再次发生请联系管理员。",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_locale_covers_every_key() {
+        for locale in Locale::ALL {
+            for msg in Msg::ALL {
+                let count = locale.table().iter().filter(|(m, _)| m == msg).count();
+                assert_eq!(count, 1, "{locale:?} has {count} entries for {msg:?}");
+            }
+        }
+    }
+}
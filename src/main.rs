@@ -9,29 +9,47 @@ use std::future::IntoFuture as _;
 use tokio::net::TcpListener;
 use tokio_util::sync::CancellationToken;
 
+mod auth;
 mod cmds;
+mod config;
 mod db;
+mod diagnose;
 mod ea;
+mod locale;
+mod metrics;
 mod nano;
 mod routes;
+mod state;
+mod sync;
+mod templates;
 mod verify;
+mod webhooks;
 
 const FUZZY_THRESHOLD: f32 = 0.5;
 
 /// Program data, which is stored and accessible in all command invocations
+#[derive(Clone)]
 struct Data {
-    au_ch_id: serenity::ChannelId,
+    /// Seconds a user is locked out for after too many failed attempts
+    attempt_cooldown: i64,
+    /// Seconds failed attempts are counted over
+    attempt_window: i64,
+    auth: auth::Auth,
+    committee: serenity::RoleId,
+    config: config::Config,
     db: sqlx::SqlitePool,
-    ea_key: String,
-    ea_url: String,
-    fresher_pg: serenity::RoleId,
-    fresher_ug: serenity::RoleId,
-    gaijin: serenity::RoleId,
-    gn_ch_id: serenity::ChannelId,
-    member: serenity::RoleId,
-    non_member: serenity::RoleId,
-    old_member: serenity::RoleId,
-    server: serenity::GuildId,
+    ea: std::sync::Arc<ea::Client>,
+    login_state: state::LoginState,
+    max_attempts: i64,
+    reviewer: Option<serenity::RoleId>,
+    webhooks: webhooks::Webhooks,
+}
+
+impl Data {
+    /// Current guild roles and channels, which can change between calls
+    fn guild(&self) -> config::Guild {
+        self.config.get()
+    }
 }
 
 type ACtx<'a> = poise::ApplicationContext<'a, Data, Error>;
@@ -68,13 +86,123 @@ impl From<String> for Fresher {
     }
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+/// How a member was verified
+#[derive(Copy, Clone, Debug, Default, serde::Deserialize, serde::Serialize, sqlx::Type)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+enum Method {
+    #[default]
+    Unknown,
+    Login,
+    Membership,
+    Manual,
+    Admin,
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Method::Unknown => write!(f, "Unknown"),
+            Method::Login => write!(f, "Imperial Login"),
+            Method::Membership => write!(f, "ICAS Membership"),
+            Method::Manual => write!(f, "Manual"),
+            Method::Admin => write!(f, "Admin"),
+        }
+    }
+}
+
+impl From<String> for Method {
+    fn from(value: String) -> Self {
+        match value.to_lowercase().as_str() {
+            "login" => Self::Login,
+            "membership" => Self::Membership,
+            "manual" => Self::Manual,
+            "admin" => Self::Admin,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Verification flow with rate-limited attempts
+#[derive(Copy, Clone, Debug, serde::Serialize, sqlx::Type)]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+enum Flow {
+    Membership,
+    Manual,
+}
+
+impl std::fmt::Display for Flow {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Flow::Membership => write!(f, "Membership"),
+            Flow::Manual => write!(f, "Manual"),
+        }
+    }
+}
+
+impl From<String> for Flow {
+    fn from(value: String) -> Self {
+        match value.to_lowercase().as_str() {
+            "manual" => Self::Manual,
+            _ => Self::Membership,
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 struct Member {
     discord_id: i64,
     shortcode: String,
     nickname: String,
     realname: String,
     fresher: Fresher,
+    /// Unix timestamp of verification
+    #[serde(default)]
+    verified_at: Option<i64>,
+    #[serde(default)]
+    method: Method,
+    /// Discord ID of approving committee member, if any
+    #[serde(default)]
+    verified_by: Option<i64>,
+}
+
+/// Academic year (by start year) a member was verified or renewed for
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct MembershipYear {
+    discord_id: i64,
+    year: i64,
+    fresher: Fresher,
+}
+
+/// Union order number claimed by the member verified with it
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct OrderClaim {
+    order_no: i64,
+    discord_id: i64,
+    shortcode: String,
+    claimed_at: i64,
+}
+
+/// Format academic year from start year, e.g. 2023-24
+fn academic_year(year: i64) -> String {
+    format!("{year}-{:02}", (year + 1) % 100)
+}
+
+/// List members as mentions, truncated to fit in an embed field
+fn mentions(members: &[Member]) -> String {
+    use std::fmt::Write as _;
+    let mut s = String::new();
+    for (i, m) in members.iter().enumerate() {
+        if s.len() > 900 {
+            write!(s, " and {} more", members.len() - i).expect("String write! is infallible");
+            break;
+        }
+        write!(s, " <@{}>", m.discord_id).expect("String write! is infallible");
+    }
+    if s.is_empty() {
+        "None".to_string()
+    } else {
+        s
+    }
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
@@ -100,6 +228,119 @@ struct Gaijin {
     university: String,
 }
 
+/// Source of a change to the database, recorded in the audit log
+#[derive(Copy, Clone, Debug)]
+enum Actor<'a> {
+    User(serenity::UserId),
+    Route(&'static str),
+    /// API client signed in to the dashboard, by key name
+    Client(&'a str),
+    System,
+}
+
+impl std::fmt::Display for Actor<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Actor::User(id) => write!(f, "user:{id}"),
+            Actor::Route(route) => write!(f, "route:{route}"),
+            Actor::Client(name) => write!(f, "client:{name}"),
+            Actor::System => write!(f, "system"),
+        }
+    }
+}
+
+impl From<ACtx<'_>> for Actor<'_> {
+    fn from(ctx: ACtx<'_>) -> Self {
+        Actor::User(ctx.author().id)
+    }
+}
+
+#[derive(Debug)]
+struct AuditEntry {
+    id: i64,
+    created_at: i64,
+    actor: String,
+    action: String,
+    target: Option<i64>,
+    before: Option<String>,
+    after: Option<String>,
+}
+
+/// Failed verification attempts by a user in the current window
+#[derive(Debug, serde::Serialize)]
+struct Attempts {
+    discord_id: i64,
+    flow: Flow,
+    failures: i64,
+    window_start: i64,
+    /// Unix timestamp until which the user is locked out, if any
+    locked_until: Option<i64>,
+}
+
+/// Guild setting overriding its environment variable default
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct Setting {
+    key: String,
+    value: i64,
+    updated_at: i64,
+}
+
+/// Edited message template, replacing its default text
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct SavedTemplate {
+    key: String,
+    body: String,
+    updated_at: i64,
+}
+
+/// HTTP API client key, only the SHA-256 hash of the key itself is stored
+#[derive(Debug, serde::Serialize)]
+struct ApiKey {
+    name: String,
+    #[serde(skip)]
+    key_hash: String,
+    /// Comma-separated list of scopes
+    scopes: String,
+    created_at: i64,
+}
+
+/// Membership lookup made by an HTTP API client
+#[derive(Debug)]
+struct LookupEntry {
+    id: i64,
+    created_at: i64,
+    client: String,
+    discord_id: i64,
+    verified: bool,
+}
+
+/// Evidence and review message for an entry in the manual table
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct ReviewRequest {
+    discord_id: i64,
+    url: String,
+    /// Message in the AU channel with review buttons, if it was sent
+    channel_id: Option<i64>,
+    message_id: Option<i64>,
+    created_at: i64,
+}
+
+/// Webhook event queued for delivery to one endpoint
+#[derive(Debug, serde::Serialize)]
+struct WebhookDelivery {
+    id: i64,
+    endpoint: String,
+    event: String,
+    payload: String,
+    /// One of `pending`, `delivered` or `failed`
+    status: String,
+    attempts: i64,
+    next_attempt_at: i64,
+    last_error: Option<String>,
+    created_at: i64,
+    delivered_at: Option<i64>,
+}
+
 macro_rules! var {
     ($var: literal) => {
         std::env::var($var).context(format!("{} not found", $var))?
@@ -140,18 +381,21 @@ async fn main() -> Result<(), Error> {
     let addr = std::net::SocketAddr::from(([0, 0, 0, 0], var!("PORT", _, 6266)));
     tracing::info!("Listening on http://{addr}");
 
+    // Build program data, shared by bot and routes
+    let data = nano::data(pool).await?;
+
+    // Create Discord Bot client
+    let mut client = ClientBuilder::new(var!("DISCORD_TOKEN"), GatewayIntents::non_privileged())
+        .framework(nano::nanobot(data.clone())?)
+        .await?;
+
     // Build Axum Router
-    let router = routes::router(pool.clone())?;
+    let router = routes::router(data, client.http.clone(), client.shard_manager.clone());
 
     // Create Axum server with graceful shutdown
     let listener = TcpListener::bind(addr).await?;
     let server = axum::serve(listener, router).with_graceful_shutdown(signal);
 
-    // Create Discord Bot client
-    let mut client = ClientBuilder::new(var!("DISCORD_TOKEN"), GatewayIntents::non_privileged())
-        .framework(nano::nanobot(pool)?)
-        .await?;
-
     // Run futures
     tokio::select! {
         err = client.start_autosharded() => tracing::warn!("Discord client quit: {err:?}"),
@@ -0,0 +1,91 @@
+use crate::{Fresher, Method};
+use prometheus::{
+    register_histogram, register_int_counter, register_int_counter_vec, register_int_gauge_vec,
+    Histogram, IntCounter, IntCounterVec, IntGaugeVec,
+};
+use std::sync::LazyLock;
+
+static VERIFICATIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "nano_verifications_total",
+        "Verifications completed, by method and fresher status",
+        &["method", "fresher"]
+    )
+    .expect("metric is only registered once")
+});
+
+static FAILED_ATTEMPTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "nano_failed_attempts_total",
+        "Failed verification attempts, by reason",
+        &["reason"]
+    )
+    .expect("metric is only registered once")
+});
+
+pub(crate) static QUEUE_SIZE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register_int_gauge_vec!(
+        "nano_queue_size",
+        "Entries in the pending, manual and gaijin tables",
+        &["queue"]
+    )
+    .expect("metric is only registered once")
+});
+
+pub(crate) static EA_LATENCY: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "nano_ea_request_duration_seconds",
+        "EA API membership list request duration"
+    )
+    .expect("metric is only registered once")
+});
+
+pub(crate) static EA_ERRORS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register_int_counter!("nano_ea_errors_total", "Failed EA API requests")
+        .expect("metric is only registered once")
+});
+
+pub(crate) static HTTP_REQUESTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "nano_http_requests_total",
+        "HTTP requests, by route and status code",
+        &["route", "status"]
+    )
+    .expect("metric is only registered once")
+});
+
+pub(crate) static COMMANDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "nano_commands_total",
+        "Slash command invocations, by command",
+        &["command"]
+    )
+    .expect("metric is only registered once")
+});
+
+/// Count completed verification
+pub(crate) fn verified(method: Method, fresher: Fresher) {
+    let method = match method {
+        Method::Unknown => "unknown",
+        Method::Login => "login",
+        Method::Membership => "membership",
+        Method::Manual => "manual",
+        Method::Admin => "admin",
+    };
+    let fresher = match fresher {
+        Fresher::No => "no",
+        Fresher::YesPg => "yes_pg",
+        Fresher::YesUg => "yes_ug",
+    };
+    VERIFICATIONS.with_label_values(&[method, fresher]).inc();
+}
+
+/// Count failed verification attempt
+pub(crate) fn failed(reason: &'static str) {
+    FAILED_ATTEMPTS.with_label_values(&[reason]).inc();
+}
+
+/// Encode all metrics in the Prometheus text format
+pub(crate) fn gather() -> Result<String, prometheus::Error> {
+    prometheus::TextEncoder::new().encode_to_string(&prometheus::gather())
+}
@@ -1,4 +1,7 @@
-use crate::{var, verify, Data, Error, Fresher};
+use crate::{
+    auth, cmds::checks, config, diagnose, ea, metrics, state, sync, var, verify, webhooks, Data,
+    Error, Fresher,
+};
 use anyhow::Context as _;
 use poise::serenity_prelude::{self as serenity, FullEvent};
 use tokio::signal::ctrl_c;
@@ -6,28 +9,65 @@ use tokio::signal::ctrl_c;
 use tokio::signal::unix::{signal, SignalKind};
 use tokio_util::sync::CancellationToken;
 
-pub(crate) fn nanobot(pool: sqlx::SqlitePool) -> Result<poise::Framework<Data, Error>, Error> {
-    // Build Bot Data
-    let data = Data {
-        au_ch_id: var!("AU_CHANNEL_ID", _),
-        db: pool,
-        ea_key: var!("EA_API_KEY"),
-        ea_url: var!("EA_API_URL"),
-        fresher_pg: var!("FRESHER_PG_ID", _),
-        fresher_ug: var!("FRESHER_UG_ID", _),
-        gaijin: var!("GAIJIN_ID", _),
-        gn_ch_id: var!("GN_CHANNEL_ID", _),
-        member: var!("MEMBER_ID", _),
-        non_member: var!("NON_MEMBER_ID", _),
-        old_member: var!("OLD_MEMBER_ID", _),
-        server: var!("SERVER_ID", _),
+/// Build program data from environment, shared by the bot and the HTTP routes
+pub(crate) async fn data(pool: sqlx::SqlitePool) -> Result<Data, Error> {
+    Ok(Data {
+        attempt_cooldown: 60
+            * std::env::var("VERIFY_COOLDOWN_MINS")
+                .ok()
+                .and_then(|m| m.parse().ok())
+                .unwrap_or(60),
+        attempt_window: 60
+            * std::env::var("VERIFY_WINDOW_MINS")
+                .ok()
+                .and_then(|m| m.parse().ok())
+                .unwrap_or(60),
+        auth: auth::Auth::from_env(pool.clone())?,
+        committee: var!("COMMITTEE_ID", _),
+        config: config::Config::from_env(pool.clone()).await?,
+        db: pool.clone(),
+        ea: std::sync::Arc::new(ea::Client::from_env()?),
+        login_state: state::LoginState::from_env()?,
+        max_attempts: std::env::var("VERIFY_MAX_ATTEMPTS")
+            .ok()
+            .and_then(|a| a.parse().ok())
+            .unwrap_or(5),
+        reviewer: std::env::var("REVIEWER_ID")
+            .ok()
+            .and_then(|r| r.parse().ok()),
+        webhooks: webhooks::Webhooks::from_env(pool)?,
+    })
+}
+
+pub(crate) fn nanobot(data: Data) -> Result<poise::Framework<Data, Error>, Error> {
+    // Build EA membership sync job, disabled if interval is 0
+    let sync_hours = std::env::var("EA_SYNC_HOURS")
+        .ok()
+        .and_then(|h| h.parse::<u64>().ok())
+        .unwrap_or(24);
+    let policy = match std::env::var("EA_SYNC_POLICY") {
+        Ok(p) => p.parse()?,
+        Err(_) => sync::Policy::Flag,
     };
+    let sync = (sync_hours > 0).then(|| sync::EaSync {
+        config: data.config.clone(),
+        db: data.db.clone(),
+        ea: data.ea.clone(),
+        interval: std::time::Duration::from_secs(sync_hours * 60 * 60),
+        policy,
+    });
 
     // Build Poise Instance
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
             commands: crate::cmds::all_commands(),
             event_handler: { |c, e, f, d| Box::pin(event_handler(c, e, f, d)) },
+            pre_command: |ctx| {
+                Box::pin(async move {
+                    let command = ctx.command().qualified_name.as_str();
+                    metrics::COMMANDS.with_label_values(&[command]).inc();
+                })
+            },
             ..Default::default()
         })
         .setup(move |ctx, _, _| {
@@ -35,6 +75,13 @@ pub(crate) fn nanobot(pool: sqlx::SqlitePool) -> Result<poise::Framework<Data, E
                 ctx.set_activity(Some(serenity::ActivityData::custom(
                     "Verifying members since 2023",
                 )));
+                if let Some(sync) = sync {
+                    tokio::spawn(sync.run_periodically(ctx.http.clone()));
+                }
+                if data.webhooks.enabled() {
+                    tokio::spawn(data.webhooks.clone().run());
+                }
+                tokio::spawn(startup_check(ctx.http.clone(), data.clone()));
                 Ok(data)
             })
         })
@@ -44,6 +91,19 @@ pub(crate) fn nanobot(pool: sqlx::SqlitePool) -> Result<poise::Framework<Data, E
     Ok(framework)
 }
 
+/// Run diagnostics once connected, reporting fatal misconfigurations to the added users
+/// channel as well as the logs
+async fn startup_check(http: std::sync::Arc<serenity::Http>, data: Data) {
+    let report = diagnose::Report::run(&http, &data).await;
+    report.log();
+    if report.fatal() {
+        let msg = serenity::CreateMessage::new().embed(report.embed());
+        if let Err(e) = data.guild().au_ch_id.send_message(&http, msg).await {
+            tracing::error!("Failed to send diagnostics report: {e}");
+        }
+    }
+}
+
 async fn event_handler(
     ctx: &serenity::Context,
     event: &FullEvent,
@@ -61,10 +121,10 @@ async fn event_handler(
             match m.data.custom_id.as_str() {
                 "register.global" | "unregister.global" | "register.guild" | "unregister.guild" => {
                 }
-                "info" => verify::info(ctx, m).await?,
+                "info" => verify::info(ctx, m, data).await?,
                 "start" => verify::start(ctx, m, data, true).await?,
                 "restart" => verify::start(ctx, m, data, false).await?,
-                "login_1" => verify::login_1(ctx, m).await?,
+                "login_1" => verify::login_1(ctx, m, data).await?,
                 "login_2" => verify::login_2(ctx, m, data).await?,
                 "login_3" => verify::login_3(ctx, m).await?,
                 "login_4n" => verify::login_4(ctx, m, Fresher::No).await?,
@@ -73,18 +133,25 @@ async fn event_handler(
                 "login_5n" => verify::login_5(ctx, m, Fresher::No).await?,
                 "login_5p" => verify::login_5(ctx, m, Fresher::YesPg).await?,
                 "login_5u" => verify::login_5(ctx, m, Fresher::YesUg).await?,
-                "membership_1" => verify::membership_1(ctx, m).await?,
+                "membership_1" => verify::membership_1(ctx, m, data).await?,
                 "membership_2n" => verify::membership_2(ctx, m, data, Fresher::No).await?,
                 "membership_2p" => verify::membership_2(ctx, m, data, Fresher::YesPg).await?,
                 "membership_2u" => verify::membership_2(ctx, m, data, Fresher::YesUg).await?,
-                "manual_1" => verify::manual_1(ctx, m).await?,
+                "manual_1" => verify::manual_1(ctx, m, data).await?,
                 "manual_2n" => verify::manual_2(ctx, m, data, Fresher::No).await?,
                 "manual_2p" => verify::manual_2(ctx, m, data, Fresher::YesPg).await?,
                 "manual_2u" => verify::manual_2(ctx, m, data, Fresher::YesUg).await?,
-                id if id.starts_with("verify-") => verify::manual_4(ctx, m, data, id).await?,
+                id if id.starts_with("verify-") => {
+                    if authorised(data, m.guild_id, m.member.as_ref()) {
+                        verify::manual_4(ctx, m, data, id).await?;
+                    } else {
+                        tracing::warn!("Unauthorised {id} by {} ({})", m.user.name, m.user.id);
+                        verify::unauthorised(ctx, m).await?;
+                    }
+                }
                 _ => {
                     tracing::info!("Unknown interaction, printing:\n{m:#?}");
-                    verify::unknown(ctx, m).await?;
+                    verify::unknown(ctx, m, data).await?;
                 }
             }
         }
@@ -102,7 +169,14 @@ async fn event_handler(
                 "manual_3n" => verify::manual_3(ctx, m, data, Fresher::No).await?,
                 "manual_3p" => verify::manual_3(ctx, m, data, Fresher::YesPg).await?,
                 "manual_3u" => verify::manual_3(ctx, m, data, Fresher::YesUg).await?,
-                id if id.starts_with("manual_5-") => verify::manual_5(ctx, m, data, id).await?,
+                id if id.starts_with("manual_5-") => {
+                    if authorised(data, m.guild_id, m.member.as_ref()) {
+                        verify::manual_5(ctx, m, data, id).await?;
+                    } else {
+                        tracing::warn!("Unauthorised {id} by {} ({})", m.user.name, m.user.id);
+                        verify::unauthorised_modal(ctx, m).await?;
+                    }
+                }
                 _ => {}
             }
         }
@@ -111,6 +185,16 @@ async fn event_handler(
     Ok(())
 }
 
+/// Check if interaction was made by a reviewer in the configured server
+fn authorised(
+    data: &Data,
+    guild_id: Option<serenity::GuildId>,
+    member: Option<&serenity::Member>,
+) -> bool {
+    guild_id == Some(data.guild().server)
+        && member.is_some_and(|mm| checks::is_reviewer(data, &mm.roles))
+}
+
 pub(crate) async fn init_db(db_url: &str) -> Result<sqlx::SqlitePool, Error> {
     #[cfg(all(target_arch = "aarch64", target_os = "linux"))]
     const FUZZY: &str = "fuzzy_linux_arm64";
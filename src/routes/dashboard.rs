@@ -0,0 +1,397 @@
+use crate::{
+    auth::{self, Scope},
+    db, verify, Actor, Data, Error, Gaijin, ReviewRequest,
+};
+use axum::{
+    extract::{Form, Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use hmac::{Hmac, Mac};
+use poise::serenity_prelude::{self as serenity, EditMessage};
+use sha2::Sha256;
+use std::{collections::HashMap, fmt::Write as _, sync::Arc};
+
+const COOKIE: &str = "nano_dashboard";
+/// Seconds a dashboard session lasts before the key must be entered again
+const SESSION_TTL: i64 = 12 * 60 * 60;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+    table{border-collapse:collapse;margin-bottom:2em}\
+    th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left;vertical-align:top}\
+    form{display:inline-block;margin:0 .2em}\
+    .notice{background:#eef;padding:.5em 1em}";
+
+/// Web dashboard listing the manual, pending and gaijin tables, with the same review
+/// actions as the buttons on review request embeds
+///
+/// Sign in is with an API key with the `review` scope, which is kept as a session cookie
+/// signed with the server secret and bound to the key, so revoking the key ends its
+/// sessions
+#[derive(Clone)]
+pub(crate) struct Dashboard {
+    data: Data,
+    http: Arc<serenity::Http>,
+    /// Only send the session cookie over HTTPS, which needs a TLS-terminating proxy in
+    /// front of nano
+    secure: bool,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Login {
+    key: String,
+}
+
+#[derive(Default, serde::Deserialize)]
+pub(crate) struct Notice {
+    notice: Option<String>,
+}
+
+/// Fields for converting a manual entry to a gaijin
+#[derive(Default, serde::Deserialize)]
+pub(crate) struct ReviewForm {
+    name: Option<String>,
+    university: Option<String>,
+}
+
+impl Dashboard {
+    pub(crate) fn new(data: Data, http: Arc<serenity::Http>) -> Self {
+        let secure = std::env::var("DASHBOARD_SECURE_COOKIE").map_or(true, |s| s != "false");
+        if !secure {
+            tracing::warn!("Dashboard session cookies will be sent over plain HTTP");
+        }
+        Self { data, http, secure }
+    }
+
+    /// Session cookie attributes, with `Secure` unless disabled
+    fn cookie_attributes(&self) -> &'static str {
+        if self.secure {
+            "Path=/dashboard; HttpOnly; Secure; SameSite=Strict"
+        } else {
+            "Path=/dashboard; HttpOnly; SameSite=Strict"
+        }
+    }
+
+    /// Session signature of `payload` for the key of hash `key_hash`
+    fn mac(&self, key_hash: &str, payload: &str) -> Hmac<Sha256> {
+        self.data
+            .auth
+            .mac("session", &format!("{key_hash}.{payload}"))
+    }
+
+    /// Session cookie value `<hex key name>.<expiry>.<signature>`
+    fn session_cookie(&self, name: &str, key_hash: &str) -> String {
+        let expiry = serenity::Timestamp::now().unix_timestamp() + SESSION_TTL;
+        let payload = format!("{}.{expiry}", hex::encode(name));
+        let signature = hex::encode(self.mac(key_hash, &payload).finalize().into_bytes());
+        format!(
+            "{COOKIE}={payload}.{signature}; Max-Age={SESSION_TTL}; {}",
+            self.cookie_attributes()
+        )
+    }
+
+    /// Get client name from the session cookie, if valid and the key still has the
+    /// review scope
+    async fn session(&self, headers: &HeaderMap) -> Result<Option<String>, Error> {
+        let Some(cookie) = headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(';'))
+            .find_map(|c| c.trim().strip_prefix(&format!("{COOKIE}=")))
+        else {
+            return Ok(None);
+        };
+        let Some((payload, signature)) = cookie.rsplit_once('.') else {
+            return Ok(None);
+        };
+        let Some((name, expiry)) = payload.split_once('.') else {
+            return Ok(None);
+        };
+        let (Ok(name), Ok(expiry), Ok(signature)) = (
+            hex::decode(name).map(String::from_utf8),
+            expiry.parse::<i64>(),
+            hex::decode(signature),
+        ) else {
+            return Ok(None);
+        };
+        let Ok(name) = name else {
+            return Ok(None);
+        };
+        if expiry < serenity::Timestamp::now().unix_timestamp() {
+            return Ok(None);
+        }
+        let Some(key) = db::get_api_key_by_name(&self.data.db, &name).await? else {
+            return Ok(None);
+        };
+        if !auth::has_scope(&key, Scope::Review)
+            || self
+                .mac(&key.key_hash, payload)
+                .verify_slice(&signature)
+                .is_err()
+        {
+            return Ok(None);
+        }
+        Ok(Some(name))
+    }
+
+    /// Apply review action to manual entry, and update its review request embed
+    async fn review(
+        &self,
+        client: &str,
+        id: i64,
+        action: &str,
+        form: ReviewForm,
+    ) -> Result<String, Error> {
+        let Some(manual) = db::get_manual_by_id(&self.data.db, id).await? else {
+            return Ok(format!("No manual entry for {id}"));
+        };
+        let review = db::get_review_request_by_id(&self.data.db, id).await?;
+        let user = serenity::UserId::new(id.cast_unsigned())
+            .to_user(&self.http)
+            .await?;
+        let actor = Actor::Client(client);
+        let http = self.http.as_ref();
+
+        let embed = match action {
+            "accept" => {
+                let by = format!("Dashboard ({client})");
+                let mm = verify::accept_manual(http, &self.data, &user, actor, None).await?;
+                verify::accepted_embed(&user, &mm, &by)
+            }
+            "deny" => {
+                verify::deny_manual(http, &self.data, &user, actor).await?;
+                verify::denied_embed(&user)
+            }
+            "gaijin" => {
+                let name = form
+                    .name
+                    .filter(|n| !n.trim().is_empty())
+                    .unwrap_or(manual.realname);
+                let Some(university) = form.university.filter(|u| !u.trim().is_empty()) else {
+                    return Ok("University is required to add a gaijin".to_string());
+                };
+                let gaijin = Gaijin {
+                    discord_id: id,
+                    name: name.clone(),
+                    university: university.clone(),
+                };
+                verify::manual_to_gaijin(http, &self.data, &user, actor, gaijin).await?;
+                verify::gaijin_embed(&user, &name, &university)
+            }
+            _ => return Ok(format!("Unknown action: {action}")),
+        };
+        tracing::info!("{client} reviewed {} ({id}): {action}", user.name);
+
+        if let Some(ReviewRequest {
+            channel_id: Some(channel_id),
+            message_id: Some(message_id),
+            ..
+        }) = review
+        {
+            let edit = EditMessage::new().embed(embed).components(vec![]);
+            if let Err(e) = serenity::ChannelId::new(channel_id.cast_unsigned())
+                .edit_message(http, message_id.cast_unsigned(), edit)
+                .await
+            {
+                tracing::warn!("Failed to update review request embed for {id}: {e}");
+            }
+        }
+        Ok(format!("{action}: {} ({id})", user.name))
+    }
+
+    async fn render(&self, notice: Option<String>) -> Result<String, Error> {
+        let manual = db::get_all_manual(&self.data.db).await?;
+        let reviews = db::get_all_review_requests(&self.data.db)
+            .await?
+            .into_iter()
+            .map(|r| (r.discord_id, r))
+            .collect::<HashMap<_, _>>();
+        let pending = db::get_all_pending(&self.data.db).await?;
+        let gaijin = db::get_all_gaijin(&self.data.db).await?;
+
+        let mut body = String::new();
+        if let Some(notice) = notice {
+            write!(body, "<p class=notice>{}</p>", escape(&notice))
+                .expect("String write! is infallible");
+        }
+
+        write!(
+            body,
+            "<h2>Manual ({})</h2><table><tr><th>Discord ID</th><th>Shortcode</th>\
+            <th>Real name</th><th>Nickname</th><th>Fresher</th><th>Evidence</th>\
+            <th>Requested</th><th>Review</th></tr>",
+            manual.len()
+        )
+        .expect("String write! is infallible");
+        for m in &manual {
+            let review = reviews.get(&m.discord_id);
+            // Only link web URLs, as the URL is provided by the user
+            let evidence = review
+                .filter(|r| r.url.starts_with("https://") || r.url.starts_with("http://"))
+                .map_or("-".to_string(), |r| {
+                    let url = escape(&r.url);
+                    format!("<a href=\"{url}\" target=_blank rel=\"noopener noreferrer\">{url}</a>")
+                });
+            let requested = review
+                .and_then(|r| serenity::Timestamp::from_unix_timestamp(r.created_at).ok())
+                .map_or("-".to_string(), |t| t.to_string());
+            let action = |a: &str| format!("/dashboard/manual/{}/{a}", m.discord_id);
+            write!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{evidence}</td>\
+                <td>{requested}</td><td>\
+                <form method=post action=\"{}\"><button>Accept</button></form>\
+                <form method=post action=\"{}\"><button>Deny</button></form>\
+                <form method=post action=\"{}\">\
+                <input name=name value=\"{}\" placeholder=Name>\
+                <input name=university placeholder=University required>\
+                <button>Gaijin</button></form></td></tr>",
+                m.discord_id,
+                escape(&m.shortcode),
+                escape(&m.realname),
+                escape(&m.nickname),
+                m.fresher,
+                action("accept"),
+                action("deny"),
+                action("gaijin"),
+                escape(&m.realname),
+            )
+            .expect("String write! is infallible");
+        }
+        body.push_str("</table>");
+
+        write!(
+            body,
+            "<h2>Pending ({})</h2><table><tr><th>Discord ID</th><th>Shortcode</th>\
+            <th>Real name</th></tr>",
+            pending.len()
+        )
+        .expect("String write! is infallible");
+        for p in &pending {
+            write!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                p.discord_id,
+                escape(&p.shortcode),
+                escape(&p.realname)
+            )
+            .expect("String write! is infallible");
+        }
+        body.push_str("</table>");
+
+        write!(
+            body,
+            "<h2>Gaijin ({})</h2><table><tr><th>Discord ID</th><th>Name</th>\
+            <th>University</th></tr>",
+            gaijin.len()
+        )
+        .expect("String write! is infallible");
+        for g in &gaijin {
+            write!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                g.discord_id,
+                escape(&g.name),
+                escape(&g.university)
+            )
+            .expect("String write! is infallible");
+        }
+        body.push_str("</table>");
+        body.push_str(
+            "<form method=post action=/dashboard/logout><button>Sign out</button></form>",
+        );
+        Ok(page(&body))
+    }
+}
+
+/// Escape text for HTML content and quoted attributes
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn page(body: &str) -> String {
+    format!(
+        "<!doctype html><html><head><meta charset=utf-8><title>Nano dashboard</title>\
+        <style>{STYLE}</style></head><body><h1>Nano dashboard</h1>{body}</body></html>"
+    )
+}
+
+fn login_page(error: Option<&str>) -> Html<String> {
+    let error = error.map_or(String::new(), |e| format!("<p class=notice>{e}</p>"));
+    Html(page(&format!(
+        "{error}<form method=post action=/dashboard/login>\
+        <input type=password name=key placeholder=\"API key\" required> \
+        <button>Sign in</button></form>"
+    )))
+}
+
+/// Redirect to dashboard, showing notice
+fn redirect(notice: &str) -> Response {
+    let notice = url::form_urlencoded::byte_serialize(notice.as_bytes()).collect::<String>();
+    Redirect::to(&format!("/dashboard?notice={notice}")).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn dashboard_index(
+    dashboard: Dashboard,
+    headers: HeaderMap,
+    Query(Notice { notice }): Query<Notice>,
+) -> Response {
+    match dashboard.session(&headers).await {
+        Ok(Some(_)) => match dashboard.render(notice).await {
+            Ok(page) => Html(page).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+        },
+        Ok(None) => login_page(None).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn dashboard_login(dashboard: Dashboard, Form(login): Form<Login>) -> Response {
+    let key = db::get_api_key_by_hash(&dashboard.data.db, &auth::hash_key(login.key.trim())).await;
+    match key {
+        Ok(Some(key)) if auth::has_scope(&key, Scope::Review) => {
+            tracing::info!("{} signed in to dashboard", key.name);
+            let cookie = dashboard.session_cookie(&key.name, &key.key_hash);
+            ([(header::SET_COOKIE, cookie)], Redirect::to("/dashboard")).into_response()
+        }
+        Ok(_) => {
+            let error = Some("Invalid key, or key does not have the review scope");
+            (StatusCode::UNAUTHORIZED, login_page(error)).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn dashboard_logout(dashboard: Dashboard) -> Response {
+    let cookie = format!("{COOKIE}=; Max-Age=0; {}", dashboard.cookie_attributes());
+    ([(header::SET_COOKIE, cookie)], Redirect::to("/dashboard")).into_response()
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn dashboard_review(
+    dashboard: Dashboard,
+    Path((id, action)): Path<(i64, String)>,
+    headers: HeaderMap,
+    Form(form): Form<ReviewForm>,
+) -> Response {
+    let client = match dashboard.session(&headers).await {
+        Ok(Some(client)) => client,
+        Ok(None) => return StatusCode::UNAUTHORIZED.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    };
+    match dashboard.review(&client, id, &action, form).await {
+        Ok(notice) => redirect(&notice),
+        Err(e) => {
+            tracing::error!("Dashboard {action} for {id} failed: {e}");
+            redirect(&format!("{action} failed for {id}: {e}"))
+        }
+    }
+}
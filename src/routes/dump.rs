@@ -0,0 +1,128 @@
+use crate::{db, Error};
+use serde_json::{json, Value};
+
+/// Converts a dump to the next version
+type Upgrade = fn(Value) -> Result<Value, String>;
+
+/// Converters from each dump version to the next, starting from version 1, add one
+/// whenever the structure of an exported table changes
+const UPGRADES: &[Upgrade] = &[v1_to_v2, v2_to_v3, v3_to_v4];
+
+/// Version of the full JSON export
+pub(crate) const DUMP_VERSION: usize = UPGRADES.len() + 1;
+
+/// Metadata at the start of a full export, before the `db` field
+#[derive(serde::Serialize)]
+pub(crate) struct DumpMeta {
+    version: usize,
+    /// Unix timestamp of the export
+    exported_at: i64,
+    nano_version: &'static str,
+    counts: DumpCounts,
+}
+
+#[derive(serde::Serialize)]
+struct DumpCounts {
+    pending: i64,
+    manual: i64,
+    members: i64,
+    extras: i64,
+    membership_years: i64,
+    order_claims: i64,
+    review_requests: i64,
+    settings: i64,
+    templates: i64,
+}
+
+impl DumpMeta {
+    pub(crate) async fn new(pool: &sqlx::SqlitePool) -> Result<Self, Error> {
+        Ok(Self {
+            version: DUMP_VERSION,
+            exported_at: poise::serenity_prelude::Timestamp::now().unix_timestamp(),
+            nano_version: env!("CARGO_PKG_VERSION"),
+            counts: DumpCounts {
+                pending: db::count_pending(pool).await?,
+                manual: db::count_manual(pool).await?,
+                members: db::count_members(pool).await?,
+                extras: db::count_gaijin(pool).await?,
+                membership_years: db::count_membership_years(pool).await?,
+                order_claims: db::count_order_claims(pool).await?,
+                review_requests: db::count_review_requests(pool).await?,
+                settings: db::count_settings(pool).await?,
+                templates: db::count_templates(pool).await?,
+            },
+        })
+    }
+}
+
+/// Upgrade a dump of any supported version to the current version, returning its tables
+pub(crate) fn upgrade(mut dump: Value) -> Result<Value, String> {
+    let mut version = match dump.get("version") {
+        // Version 1 dumps are the bare tables, without an envelope
+        None => 1,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| usize::try_from(v).ok())
+            .ok_or(format!("Invalid dump version: {v}"))?,
+    };
+    if version == 0 || version > DUMP_VERSION {
+        return Err(format!(
+            "Unsupported dump version {version}, expected 1 to {DUMP_VERSION}"
+        ));
+    }
+    while version < DUMP_VERSION {
+        dump = UPGRADES[version - 1](dump)?;
+        version += 1;
+    }
+    match dump {
+        Value::Object(mut dump) => dump.remove("db").ok_or("Dump has no db".to_string()),
+        _ => Err("Dump is not an object".to_string()),
+    }
+}
+
+/// Version 2 wraps the tables in an envelope with metadata, and members gain
+/// verification details
+fn v1_to_v2(mut db: Value) -> Result<Value, String> {
+    let Some(members) = db.get_mut("members").and_then(Value::as_array_mut) else {
+        return Err("Version 1 dump has no members table".to_string());
+    };
+    for member in members.iter_mut().filter_map(Value::as_object_mut) {
+        for field in ["verified_at", "verified_by"] {
+            member.entry(field).or_insert(Value::Null);
+        }
+        member.entry("method").or_insert(json!("Unknown"));
+    }
+    Ok(json!({
+        "version": 2,
+        "exported_at": null,
+        "nano_version": null,
+        "counts": null,
+        "db": db,
+    }))
+}
+
+/// Version 3 adds the academic years each member was verified or renewed for, which
+/// version 2 dumps did not record, so members imported from them need renewing before
+/// the next `/rollover`
+fn v2_to_v3(mut dump: Value) -> Result<Value, String> {
+    let Some(db) = dump.get_mut("db").and_then(Value::as_object_mut) else {
+        return Err("Version 2 dump has no db".to_string());
+    };
+    db.entry("membership_years").or_insert(json!([]));
+    dump["version"] = json!(3);
+    Ok(dump)
+}
+
+/// Version 4 adds claimed order numbers, manual review evidence, guild settings and
+/// edited templates, so a dump covers every table except operational state (audit log,
+/// verification attempts, login states, API keys, webhooks and lookup logs)
+fn v3_to_v4(mut dump: Value) -> Result<Value, String> {
+    let Some(db) = dump.get_mut("db").and_then(Value::as_object_mut) else {
+        return Err("Version 3 dump has no db".to_string());
+    };
+    for table in ["order_claims", "review_requests", "settings", "templates"] {
+        db.entry(table).or_insert(json!([]));
+    }
+    dump["version"] = json!(4);
+    Ok(dump)
+}
@@ -0,0 +1,435 @@
+use crate::{
+    auth::{Auth, Scope},
+    db,
+    routes::DumpMeta,
+    Error, Fresher,
+};
+use axum::{
+    body::Body,
+    extract::Query,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use futures::{stream::BoxStream, TryStreamExt as _};
+use std::fmt::Write as _;
+use tokio::sync::mpsc;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Format {
+    /// Single JSON document, a versioned dump of all tables if no table is selected
+    #[default]
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header row, requires a table
+    Csv,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Table {
+    Pending,
+    Manual,
+    Members,
+    Extras,
+    #[serde(rename = "membership_years")]
+    MembershipYears,
+    #[serde(rename = "order_claims")]
+    OrderClaims,
+    #[serde(rename = "review_requests")]
+    ReviewRequests,
+    Settings,
+    Templates,
+}
+
+impl Table {
+    const ALL: [Table; 9] = [
+        Table::Pending,
+        Table::Manual,
+        Table::Members,
+        Table::Extras,
+        Table::MembershipYears,
+        Table::OrderClaims,
+        Table::ReviewRequests,
+        Table::Settings,
+        Table::Templates,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Table::Pending => "pending",
+            Table::Manual => "manual",
+            Table::Members => "members",
+            Table::Extras => "extras",
+            Table::MembershipYears => "membership_years",
+            Table::OrderClaims => "order_claims",
+            Table::ReviewRequests => "review_requests",
+            Table::Settings => "settings",
+            Table::Templates => "templates",
+        }
+    }
+
+    fn columns(self) -> &'static [&'static str] {
+        match self {
+            Table::Pending => &["discord_id", "shortcode", "realname"],
+            Table::Manual => &["discord_id", "shortcode", "nickname", "realname", "fresher"],
+            Table::Members => &[
+                "discord_id",
+                "shortcode",
+                "nickname",
+                "realname",
+                "fresher",
+                "verified_at",
+                "method",
+                "verified_by",
+            ],
+            Table::Extras => &["discord_id", "name", "university"],
+            Table::MembershipYears => &["discord_id", "year", "fresher"],
+            Table::OrderClaims => &["order_no", "discord_id", "shortcode", "claimed_at"],
+            Table::ReviewRequests => &[
+                "discord_id",
+                "url",
+                "channel_id",
+                "message_id",
+                "created_at",
+            ],
+            Table::Settings => &["key", "value", "updated_at"],
+            Table::Templates => &["key", "body", "updated_at"],
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct ExportQuery {
+    key: Option<String>,
+    #[serde(default)]
+    format: Format,
+    /// Export a single table, instead of all tables
+    table: Option<Table>,
+    /// Comma-separated columns to include, requires a table
+    columns: Option<String>,
+    /// Only members or manual entries with this fresher status (`no`, `yes_pg`, `yes_ug`)
+    fresher: Option<String>,
+    /// Only members verified on or after this date (YYYY-MM-DD)
+    verified_after: Option<String>,
+}
+
+/// Validated export options
+struct Export {
+    format: Format,
+    tables: Vec<Table>,
+    columns: Option<Vec<String>>,
+    fresher: Option<Fresher>,
+    verified_after: Option<String>,
+}
+
+impl TryFrom<ExportQuery> for Export {
+    type Error = String;
+
+    fn try_from(q: ExportQuery) -> Result<Self, Self::Error> {
+        let tables = q.table.map_or(Table::ALL.to_vec(), |t| vec![t]);
+        if q.format == Format::Csv && q.table.is_none() {
+            return Err("CSV export requires a table".to_string());
+        }
+        let columns = match (q.columns, q.table) {
+            (None, _) => None,
+            (Some(_), None) => return Err("Column selection requires a table".to_string()),
+            (Some(columns), Some(table)) => {
+                let columns = columns
+                    .split(',')
+                    .map(|c| c.trim().to_string())
+                    .collect::<Vec<_>>();
+                if let Some(c) = columns
+                    .iter()
+                    .find(|c| !table.columns().contains(&c.as_str()))
+                {
+                    return Err(format!("Unknown column for {}: {c}", table.name()));
+                }
+                Some(columns)
+            }
+        };
+        let fresher = match q.fresher.as_deref() {
+            None => None,
+            Some("no") => Some(Fresher::No),
+            Some("yes_pg") => Some(Fresher::YesPg),
+            Some("yes_ug") => Some(Fresher::YesUg),
+            Some(f) => return Err(format!("Invalid fresher filter: {f}")),
+        };
+        if fresher.is_some()
+            && tables
+                .iter()
+                .any(|t| ![Table::Members, Table::Manual].contains(t))
+        {
+            return Err("Fresher filter only applies to members and manual".to_string());
+        }
+        if let Some(date) = &q.verified_after {
+            if tables != [Table::Members] {
+                return Err("Verified after filter only applies to members".to_string());
+            }
+            let b = date.as_bytes();
+            let valid = b.len() == 10
+                && b.iter().enumerate().all(|(i, c)| {
+                    if i == 4 || i == 7 {
+                        *c == b'-'
+                    } else {
+                        c.is_ascii_digit()
+                    }
+                });
+            if !valid {
+                return Err(format!("Invalid date, expected YYYY-MM-DD: {date}"));
+            }
+        }
+        Ok(Self {
+            format: q.format,
+            tables,
+            columns,
+            fresher,
+            verified_after: q.verified_after,
+        })
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn export(
+    pool: sqlx::SqlitePool,
+    auth: Auth,
+    headers: HeaderMap,
+    query: Query<ExportQuery>,
+) -> impl IntoResponse {
+    let Query(query) = query;
+    if let Err(status) = auth
+        .check(Scope::Export, &headers, &[], query.key.as_deref())
+        .await
+    {
+        return status.into_response();
+    }
+
+    let export = match Export::try_from(query) {
+        Ok(export) => export,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let content_type = match export.format {
+        Format::Json => "application/json",
+        Format::Ndjson => "application/x-ndjson",
+        Format::Csv => "text/csv",
+    };
+    let filename = match export.tables.as_slice() {
+        [table] => table.name(),
+        _ => "nano",
+    };
+    let extension = match export.format {
+        Format::Json => "json",
+        Format::Ndjson => "ndjson",
+        Format::Csv => "csv",
+    };
+    let disposition = format!("attachment; filename=\"{filename}.{extension}\"");
+
+    // Rows are sent through a channel as they are read, so the response is streamed
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        if let Err(e) = write_export(&pool, export, &tx).await {
+            if tx.is_closed() {
+                tracing::info!("Export cancelled, client disconnected");
+                return;
+            }
+            tracing::error!("Export failed: {e}");
+            let _ = tx.send(Err(e)).await;
+        }
+    });
+    let stream = futures::stream::unfold(rx, |mut rx| async {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response()
+}
+
+type Sender = mpsc::Sender<Result<String, Error>>;
+
+/// Read selected tables from the database, sending encoded chunks until done or the
+/// client disconnects
+async fn write_export(pool: &sqlx::SqlitePool, export: Export, tx: &Sender) -> Result<(), Error> {
+    let multiple = export.tables.len() > 1;
+    if export.format == Format::Json && multiple {
+        // Full JSON export is a versioned dump, with the tables in the `db` field
+        let mut meta = serde_json::to_string(&DumpMeta::new(pool).await?)?;
+        meta.pop();
+        meta.push_str(",\"db\":{");
+        tx.send(Ok(meta)).await?;
+    }
+    for (i, &table) in export.tables.iter().enumerate() {
+        let mut encoder = Encoder {
+            format: export.format,
+            table,
+            columns: export.columns.clone(),
+            tag: multiple && export.format == Format::Ndjson,
+            first: true,
+        };
+        let start = match export.format {
+            Format::Json if multiple => {
+                let comma = if i == 0 { "" } else { "," };
+                format!("{comma}\"{}\":[", table.name())
+            }
+            Format::Json => "[".to_string(),
+            Format::Ndjson => String::new(),
+            Format::Csv => encoder.header(),
+        };
+        tx.send(Ok(start)).await?;
+        match table {
+            Table::Pending => send_rows(db::stream_pending(pool), &mut encoder, tx).await?,
+            Table::Manual => {
+                let rows = db::stream_manual(pool, export.fresher);
+                send_rows(rows, &mut encoder, tx).await?;
+            }
+            Table::Members => {
+                let rows =
+                    db::stream_members(pool, export.fresher, export.verified_after.as_deref());
+                send_rows(rows, &mut encoder, tx).await?;
+            }
+            Table::Extras => send_rows(db::stream_gaijin(pool), &mut encoder, tx).await?,
+            Table::MembershipYears => {
+                let rows = db::stream_membership_years(pool);
+                send_rows(rows, &mut encoder, tx).await?;
+            }
+            Table::OrderClaims => {
+                send_rows(db::stream_order_claims(pool), &mut encoder, tx).await?;
+            }
+            Table::ReviewRequests => {
+                send_rows(db::stream_review_requests(pool), &mut encoder, tx).await?;
+            }
+            Table::Settings => send_rows(db::stream_settings(pool), &mut encoder, tx).await?,
+            Table::Templates => send_rows(db::stream_templates(pool), &mut encoder, tx).await?,
+        }
+        if export.format == Format::Json {
+            tx.send(Ok("]".to_string())).await?;
+        }
+    }
+    if export.format == Format::Json && multiple {
+        tx.send(Ok("}}".to_string())).await?;
+    }
+    Ok(())
+}
+
+/// Full JSON export of the database
+#[cfg(test)]
+pub(crate) async fn dump(pool: &sqlx::SqlitePool) -> serde_json::Value {
+    let export = Export::try_from(ExportQuery {
+        key: None,
+        format: Format::Json,
+        table: None,
+        columns: None,
+        fresher: None,
+        verified_after: None,
+    })
+    .expect("full export is valid");
+    let (tx, mut rx) = mpsc::channel(64);
+    let write = async move { write_export(pool, export, &tx).await };
+    let read = async {
+        let mut out = String::new();
+        while let Some(chunk) = rx.recv().await {
+            out.push_str(&chunk.expect("export chunk is sent"));
+        }
+        out
+    };
+    let (written, out) = tokio::join!(write, read);
+    written.expect("export succeeds");
+    serde_json::from_str(&out).expect("export is valid JSON")
+}
+
+async fn send_rows<T: serde::Serialize>(
+    mut rows: BoxStream<'_, Result<T, sqlx::Error>>,
+    encoder: &mut Encoder,
+    tx: &Sender,
+) -> Result<(), Error> {
+    while let Some(row) = rows.try_next().await? {
+        tx.send(Ok(encoder.row(&row)?)).await?;
+    }
+    Ok(())
+}
+
+/// Encodes rows of a single table in the export format
+struct Encoder {
+    format: Format,
+    table: Table,
+    columns: Option<Vec<String>>,
+    /// Add table name to each row, for NDJSON exports of multiple tables
+    tag: bool,
+    first: bool,
+}
+
+impl Encoder {
+    fn columns(&self) -> Vec<&str> {
+        self.columns.as_ref().map_or_else(
+            || self.table.columns().to_vec(),
+            |c| c.iter().map(String::as_str).collect(),
+        )
+    }
+
+    fn header(&self) -> String {
+        let mut header = self.columns().join(",");
+        header.push('\n');
+        header
+    }
+
+    fn row(&mut self, row: &impl serde::Serialize) -> Result<String, Error> {
+        let value = serde_json::to_value(row)?;
+        let mut out = String::new();
+        match self.format {
+            Format::Json | Format::Ndjson => {
+                if self.format == Format::Json && !self.first {
+                    out.push(',');
+                }
+                out.push('{');
+                if self.tag {
+                    write!(out, "\"table\":\"{}\",", self.table.name())
+                        .expect("String write! is infallible");
+                }
+                let fields = self
+                    .columns()
+                    .iter()
+                    .map(|&c| format!("\"{c}\":{}", value[c]))
+                    .collect::<Vec<_>>();
+                out.push_str(&fields.join(","));
+                out.push('}');
+                if self.format == Format::Ndjson {
+                    out.push('\n');
+                }
+            }
+            Format::Csv => {
+                let fields = self
+                    .columns()
+                    .iter()
+                    .map(|&c| csv_field(&value[c]))
+                    .collect::<Vec<_>>();
+                out.push_str(&fields.join(","));
+                out.push('\n');
+            }
+        }
+        self.first = false;
+        Ok(out)
+    }
+}
+
+/// Format value as a CSV field, quoting if needed and escaping spreadsheet formulas
+fn csv_field(value: &serde_json::Value) -> String {
+    let s = match value {
+        serde_json::Value::Null => return String::new(),
+        serde_json::Value::String(s) if s.starts_with(['=', '+', '-', '@']) => format!("'{s}"),
+        serde_json::Value::String(s) => s.clone(),
+        v => v.to_string(),
+    };
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    }
+}
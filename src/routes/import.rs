@@ -0,0 +1,602 @@
+use crate::{
+    auth::{Auth, Scope},
+    config,
+    db::{self, Imported},
+    routes::dump,
+    templates::Template,
+    Actor, Error, Gaijin, ManualMember, Member, MembershipYear, OrderClaim, PendingMember,
+    ReviewRequest, SavedTemplate, Setting,
+};
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde_json::Value;
+
+/// How to handle imported rows whose Discord ID already exists
+#[derive(Copy, Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum OnConflict {
+    /// Keep the existing row
+    #[default]
+    Skip,
+    /// Replace the existing row
+    Overwrite,
+    /// Reject the whole import if any row conflicts or is invalid
+    Fail,
+}
+
+/// Import data, rows are kept as raw JSON so each can be validated separately
+#[derive(serde::Deserialize)]
+struct ImportDb {
+    #[serde(default)]
+    pending: Vec<Value>,
+    #[serde(default)]
+    manual: Vec<Value>,
+    #[serde(default)]
+    members: Vec<Value>,
+    #[serde(default)]
+    extras: Vec<Value>,
+    #[serde(default)]
+    membership_years: Vec<Value>,
+    #[serde(default)]
+    order_claims: Vec<Value>,
+    #[serde(default)]
+    review_requests: Vec<Value>,
+    #[serde(default)]
+    settings: Vec<Value>,
+    #[serde(default)]
+    templates: Vec<Value>,
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Import {
+    /// Dump from `/export`, of any supported version
+    db: Value,
+    key: Option<String>,
+    /// Validate and report without committing any changes
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    on_conflict: OnConflict,
+}
+
+#[derive(Default, serde::Serialize)]
+struct TableReport {
+    received: usize,
+    inserted: usize,
+    overwritten: usize,
+    skipped: usize,
+    rejected: usize,
+}
+
+impl TableReport {
+    fn received(received: usize) -> Self {
+        Self {
+            received,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct RejectedRow {
+    table: &'static str,
+    /// Position of the row in the submitted table
+    index: usize,
+    discord_id: Option<i64>,
+    error: String,
+}
+
+#[derive(serde::Serialize)]
+struct ImportReport {
+    dry_run: bool,
+    on_conflict: OnConflict,
+    committed: bool,
+    pending: TableReport,
+    manual: TableReport,
+    members: TableReport,
+    extras: TableReport,
+    membership_years: TableReport,
+    order_claims: TableReport,
+    review_requests: TableReport,
+    settings: TableReport,
+    templates: TableReport,
+    rejected: Vec<RejectedRow>,
+}
+
+impl ImportReport {
+    fn table(&mut self, table: &'static str) -> &mut TableReport {
+        match table {
+            "pending" => &mut self.pending,
+            "manual" => &mut self.manual,
+            "members" => &mut self.members,
+            "membership_years" => &mut self.membership_years,
+            "order_claims" => &mut self.order_claims,
+            "review_requests" => &mut self.review_requests,
+            "settings" => &mut self.settings,
+            "templates" => &mut self.templates,
+            _ => &mut self.extras,
+        }
+    }
+
+    fn reject(&mut self, table: &'static str, index: usize, id: Option<i64>, error: String) {
+        self.table(table).rejected += 1;
+        self.rejected.push(RejectedRow {
+            table,
+            index,
+            discord_id: id,
+            error,
+        });
+    }
+
+    fn record(
+        &mut self,
+        table: &'static str,
+        index: usize,
+        id: Option<i64>,
+        result: Result<Imported, Error>,
+    ) {
+        match result {
+            Ok(Imported::Inserted) => self.table(table).inserted += 1,
+            Ok(Imported::Overwritten) => self.table(table).overwritten += 1,
+            Ok(Imported::Conflict) if self.on_conflict == OnConflict::Fail => {
+                let error = "Discord ID already exists".to_string();
+                self.reject(table, index, id, error);
+            }
+            Ok(Imported::Conflict) => self.table(table).skipped += 1,
+            Err(e) => self.reject(table, index, id, e.to_string()),
+        }
+    }
+
+    /// Parse and validate row, recording it as rejected on failure
+    fn parse<T: Row>(&mut self, table: &'static str, index: usize, row: Value) -> Option<T> {
+        let id = row.get("discord_id").and_then(Value::as_i64);
+        match serde_json::from_value::<T>(row) {
+            Ok(row) => match row.validate() {
+                Ok(()) => Some(row),
+                Err(e) => {
+                    self.reject(table, index, id, e);
+                    None
+                }
+            },
+            Err(e) => {
+                self.reject(table, index, id, e.to_string());
+                None
+            }
+        }
+    }
+}
+
+/// Imported row with per-record validation
+trait Row: serde::de::DeserializeOwned {
+    fn validate(&self) -> Result<(), String>;
+}
+
+fn check_id(id: i64) -> Result<(), String> {
+    if id > 0 {
+        Ok(())
+    } else {
+        Err(format!("Invalid Discord ID: {id}"))
+    }
+}
+
+/// Shortcodes are Imperial logins like `ab123`, or CIDs like `01234567` or `AM-12345`
+/// for members without a login, as stored by [`crate::ea::Member::key`]
+fn check_shortcode(shortcode: &str) -> Result<(), String> {
+    let valid = (2..=16).contains(&shortcode.len())
+        && shortcode.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && shortcode
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid shortcode: {shortcode:?}"))
+    }
+}
+
+fn check_text(field: &str, value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        Err(format!("Empty {field}"))
+    } else {
+        Ok(())
+    }
+}
+
+impl Row for PendingMember {
+    fn validate(&self) -> Result<(), String> {
+        check_id(self.discord_id)?;
+        check_shortcode(&self.shortcode)?;
+        check_text("realname", &self.realname)
+    }
+}
+
+impl Row for ManualMember {
+    fn validate(&self) -> Result<(), String> {
+        check_id(self.discord_id)?;
+        check_shortcode(&self.shortcode)?;
+        check_text("nickname", &self.nickname)?;
+        check_text("realname", &self.realname)
+    }
+}
+
+impl Row for Member {
+    fn validate(&self) -> Result<(), String> {
+        check_id(self.discord_id)?;
+        check_shortcode(&self.shortcode)?;
+        check_text("nickname", &self.nickname)?;
+        check_text("realname", &self.realname)
+    }
+}
+
+impl Row for MembershipYear {
+    fn validate(&self) -> Result<(), String> {
+        check_id(self.discord_id)?;
+        if (2000..=2100).contains(&self.year) {
+            Ok(())
+        } else {
+            Err(format!("Invalid academic year: {}", self.year))
+        }
+    }
+}
+
+impl Row for OrderClaim {
+    fn validate(&self) -> Result<(), String> {
+        check_id(self.discord_id)?;
+        check_shortcode(&self.shortcode)?;
+        if self.order_no > 0 {
+            Ok(())
+        } else {
+            Err(format!("Invalid order number: {}", self.order_no))
+        }
+    }
+}
+
+impl Row for ReviewRequest {
+    fn validate(&self) -> Result<(), String> {
+        check_id(self.discord_id)?;
+        check_text("url", &self.url)
+    }
+}
+
+impl Row for Setting {
+    fn validate(&self) -> Result<(), String> {
+        if config::Key::from_key(&self.key).is_none() {
+            return Err(format!("Unknown setting: {}", self.key));
+        }
+        if self.value > 0 {
+            Ok(())
+        } else {
+            Err(format!("Invalid ID for {}: {}", self.key, self.value))
+        }
+    }
+}
+
+impl Row for SavedTemplate {
+    fn validate(&self) -> Result<(), String> {
+        let Some((template, _)) = Template::from_locale_key(&self.key) else {
+            return Err(format!("Unknown template: {}", self.key));
+        };
+        template.validate(&self.body)
+    }
+}
+
+impl Row for Gaijin {
+    fn validate(&self) -> Result<(), String> {
+        check_id(self.discord_id)?;
+        check_text("name", &self.name)?;
+        check_text("university", &self.university)
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn import(
+    pool: sqlx::SqlitePool,
+    auth: Auth,
+    config: config::Config,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Ok(Import {
+        db,
+        key,
+        dry_run,
+        on_conflict,
+    }) = serde_json::from_slice(&body)
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    if let Err(status) = auth
+        .check(Scope::Import, &headers, &body, key.as_deref())
+        .await
+    {
+        return status.into_response();
+    }
+
+    let db = match dump::upgrade(db).and_then(|db| {
+        serde_json::from_value::<ImportDb>(db).map_err(|e| format!("Invalid dump: {e}"))
+    }) {
+        Ok(db) => db,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    match import_db(&pool, db, dry_run, on_conflict).await {
+        Ok(report) => {
+            let settings = report.settings.inserted + report.settings.overwritten;
+            if report.committed && settings > 0 {
+                if let Err(e) = config.load().await {
+                    tracing::error!("Failed to apply imported settings: {e}");
+                }
+            }
+            tracing::info!(
+                "Import (dry run: {dry_run}, committed: {}): {} rows rejected",
+                report.committed,
+                report.rejected.len()
+            );
+            let status = if on_conflict == OnConflict::Fail && !report.rejected.is_empty() {
+                StatusCode::CONFLICT
+            } else {
+                StatusCode::OK
+            };
+            (status, Json(report)).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    }
+}
+
+/// Import all rows in a single transaction, only committed if not a dry run and
+/// not failed by the conflict policy
+async fn import_db(
+    pool: &sqlx::SqlitePool,
+    db: ImportDb,
+    dry_run: bool,
+    on_conflict: OnConflict,
+) -> Result<ImportReport, Error> {
+    let actor = Actor::Route("/import");
+    let overwrite = on_conflict == OnConflict::Overwrite;
+    let mut report = ImportReport {
+        dry_run,
+        on_conflict,
+        committed: false,
+        pending: TableReport::received(db.pending.len()),
+        manual: TableReport::received(db.manual.len()),
+        members: TableReport::received(db.members.len()),
+        extras: TableReport::received(db.extras.len()),
+        membership_years: TableReport::received(db.membership_years.len()),
+        order_claims: TableReport::received(db.order_claims.len()),
+        review_requests: TableReport::received(db.review_requests.len()),
+        settings: TableReport::received(db.settings.len()),
+        templates: TableReport::received(db.templates.len()),
+        rejected: vec![],
+    };
+
+    let mut tx = pool.begin().await?;
+
+    for (i, row) in db.extras.into_iter().enumerate() {
+        if let Some(g) = report.parse::<Gaijin>("extras", i, row) {
+            let id = g.discord_id;
+            let result = db::import_gaijin(&mut tx, actor, g, overwrite).await;
+            report.record("extras", i, Some(id), result);
+        }
+    }
+
+    for (i, row) in db.members.into_iter().enumerate() {
+        if let Some(m) = report.parse::<Member>("members", i, row) {
+            let id = m.discord_id;
+            let result = db::import_member(&mut tx, actor, m, overwrite).await;
+            report.record("members", i, Some(id), result);
+        }
+    }
+
+    for (i, row) in db.membership_years.into_iter().enumerate() {
+        if let Some(y) = report.parse::<MembershipYear>("membership_years", i, row) {
+            let id = y.discord_id;
+            let result = db::import_membership_year(&mut tx, actor, y, overwrite).await;
+            report.record("membership_years", i, Some(id), result);
+        }
+    }
+
+    for (i, row) in db.order_claims.into_iter().enumerate() {
+        if let Some(c) = report.parse::<OrderClaim>("order_claims", i, row) {
+            let id = c.discord_id;
+            let result = db::import_order_claim(&mut tx, actor, c, overwrite).await;
+            report.record("order_claims", i, Some(id), result);
+        }
+    }
+
+    for (i, row) in db.manual.into_iter().enumerate() {
+        if let Some(m) = report.parse::<ManualMember>("manual", i, row) {
+            let id = m.discord_id;
+            let result = db::import_manual(&mut tx, actor, m, overwrite).await;
+            report.record("manual", i, Some(id), result);
+        }
+    }
+
+    for (i, row) in db.review_requests.into_iter().enumerate() {
+        if let Some(r) = report.parse::<ReviewRequest>("review_requests", i, row) {
+            let id = r.discord_id;
+            let result = db::import_review_request(&mut tx, actor, r, overwrite).await;
+            report.record("review_requests", i, Some(id), result);
+        }
+    }
+
+    for (i, row) in db.pending.into_iter().enumerate() {
+        if let Some(p) = report.parse::<PendingMember>("pending", i, row) {
+            let id = p.discord_id;
+            let result = db::import_pending(&mut tx, actor, p, overwrite).await;
+            report.record("pending", i, Some(id), result);
+        }
+    }
+
+    for (i, row) in db.settings.into_iter().enumerate() {
+        if let Some(s) = report.parse::<Setting>("settings", i, row) {
+            let result = db::import_setting(&mut tx, actor, s, overwrite).await;
+            report.record("settings", i, None, result);
+        }
+    }
+
+    for (i, row) in db.templates.into_iter().enumerate() {
+        if let Some(t) = report.parse::<SavedTemplate>("templates", i, row) {
+            let result = db::import_template(&mut tx, actor, t, overwrite).await;
+            report.record("templates", i, None, result);
+        }
+    }
+
+    let failed = on_conflict == OnConflict::Fail && !report.rejected.is_empty();
+    if dry_run || failed {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+        report.committed = true;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, ea, routes::export, Fresher, Method};
+
+    fn member(id: i64, shortcode: String) -> Member {
+        Member {
+            discord_id: id,
+            shortcode,
+            nickname: format!("Nick {id}"),
+            realname: format!("Name {id}"),
+            fresher: Fresher::No,
+            verified_at: Some(1_700_000_000),
+            method: Method::Membership,
+            verified_by: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn export_reimports_logins_and_cids() {
+        let source = test_pool().await;
+        let cid = ea::Member {
+            first_name: "First".to_string(),
+            surname: "Last".to_string(),
+            cid: "01234567".to_string(),
+            login: String::new(),
+            order_no: 1,
+        };
+        for m in [member(1, "ab123".to_string()), member(2, cid.key())] {
+            db::insert_member(&source, Actor::System, m).await.unwrap();
+        }
+        let exported = export::dump(&source).await;
+
+        let target = test_pool().await;
+        let db = serde_json::from_value(dump::upgrade(exported.clone()).unwrap()).unwrap();
+        let report = import_db(&target, db, false, OnConflict::Fail)
+            .await
+            .unwrap();
+        assert!(report.committed);
+        assert!(report.rejected.is_empty());
+        assert_eq!(report.members.inserted, 2);
+        assert_eq!(export::dump(&target).await["db"], exported["db"]);
+    }
+
+    /// Import upgraded dump into a fresh database, failing on any rejected row
+    async fn import_fresh(dump: Value) -> sqlx::SqlitePool {
+        let pool = test_pool().await;
+        let db = serde_json::from_value(dump::upgrade(dump).unwrap()).unwrap();
+        let report = import_db(&pool, db, false, OnConflict::Fail).await.unwrap();
+        assert!(report.committed);
+        assert!(report.rejected.is_empty());
+        pool
+    }
+
+    #[tokio::test]
+    async fn v1_dump_upgrades_imports_and_exports() {
+        let v1 = serde_json::json!({
+            "pending": [{ "discord_id": 3, "shortcode": "ef789", "realname": "Pending" }],
+            "manual": [{
+                "discord_id": 2,
+                "shortcode": "cd456",
+                "nickname": "Manual",
+                "realname": "Manual Name",
+                "fresher": "YesPg",
+            }],
+            "members": [{
+                "discord_id": 1,
+                "shortcode": "ab123",
+                "nickname": "Nick",
+                "realname": "Real Name",
+                "fresher": "No",
+            }],
+            "extras": [{ "discord_id": 4, "name": "Gaijin", "university": "UCL" }],
+        });
+        let pool = import_fresh(v1.clone()).await;
+        let exported = export::dump(&pool).await;
+        assert_eq!(exported["version"], dump::DUMP_VERSION);
+
+        let mut expected = v1;
+        expected["members"][0]["verified_at"] = Value::Null;
+        expected["members"][0]["method"] = "Unknown".into();
+        expected["members"][0]["verified_by"] = Value::Null;
+        for table in [
+            "membership_years",
+            "order_claims",
+            "review_requests",
+            "settings",
+            "templates",
+        ] {
+            expected[table] = serde_json::json!([]);
+        }
+        assert_eq!(exported["db"], expected);
+    }
+
+    #[tokio::test]
+    async fn export_of_every_table_reimports_unchanged() {
+        let source = test_pool().await;
+        let actor = Actor::System;
+        db::insert_member(&source, actor, member(1, "ab123".to_string()))
+            .await
+            .unwrap();
+        db::insert_member_claiming_order(&source, actor, member(2, "cd456".to_string()), 42)
+            .await
+            .unwrap();
+        let manual = ManualMember {
+            discord_id: 3,
+            shortcode: "ef789".to_string(),
+            nickname: "Manual".to_string(),
+            realname: "Manual Name".to_string(),
+            fresher: Fresher::YesUg,
+        };
+        db::insert_manual(&source, actor, manual).await.unwrap();
+        db::insert_review_request(&source, 3, "https://example.com/a.png", Some(5), Some(6))
+            .await
+            .unwrap();
+        let pending = PendingMember {
+            discord_id: 4,
+            shortcode: "gh012".to_string(),
+            realname: "Pending".to_string(),
+        };
+        db::insert_pending(&source, actor, pending).await.unwrap();
+        let gaijin = Gaijin {
+            discord_id: 5,
+            name: "Gaijin".to_string(),
+            university: "UCL".to_string(),
+        };
+        db::insert_gaijin(&source, actor, gaijin).await.unwrap();
+        db::set_setting(&source, actor, config::Key::Member.key(), 7)
+            .await
+            .unwrap();
+        db::set_template(&source, actor, "welcome.zh-CN", "欢迎 {user}")
+            .await
+            .unwrap();
+
+        let exported = export::dump(&source).await;
+        for (table, rows) in exported["db"].as_object().unwrap() {
+            let rows = rows.as_array().unwrap().len();
+            assert!(rows > 0, "{table}");
+            assert_eq!(exported["counts"][table], rows, "{table}");
+        }
+        let target = import_fresh(exported.clone()).await;
+        assert_eq!(export::dump(&target).await["db"], exported["db"]);
+    }
+}
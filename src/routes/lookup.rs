@@ -0,0 +1,172 @@
+use crate::{
+    auth::{Auth, Scope},
+    db, Error, Fresher,
+};
+use axum::{
+    body::Bytes,
+    extract::Path,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Maximum Discord IDs in a batch lookup
+const MAX_BATCH: usize = 100;
+const RATE_WINDOW: Duration = Duration::from_mins(1);
+
+/// Requests allowed per client in each window, counted from the client's first request
+#[derive(Clone)]
+struct RateLimit {
+    limit: u32,
+    windows: Arc<Mutex<HashMap<String, (Instant, u32)>>>,
+}
+
+impl RateLimit {
+    /// Count request from client, returning seconds until the next window if over the limit
+    fn check(&self, client: &str) -> Result<(), u64> {
+        let mut windows = self
+            .windows
+            .lock()
+            .expect("rate limit lock is never poisoned");
+        let now = Instant::now();
+        let (start, count) = windows.entry(client.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= RATE_WINDOW {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.limit {
+            let remaining = RATE_WINDOW.saturating_sub(now.duration_since(*start));
+            return Err(remaining.as_secs() + 1);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+/// Read-only membership lookups for other services, with per-client rate limits and
+/// an access log of who was looked up
+#[derive(Clone)]
+pub(crate) struct Lookups {
+    pool: sqlx::SqlitePool,
+    auth: Auth,
+    rate_limit: RateLimit,
+}
+
+#[derive(serde::Serialize)]
+struct Lookup {
+    discord_id: i64,
+    verified: bool,
+    fresher: bool,
+}
+
+#[derive(serde::Deserialize)]
+struct BatchLookup {
+    ids: Vec<i64>,
+}
+
+impl Lookups {
+    pub(crate) fn from_env(pool: sqlx::SqlitePool, auth: Auth) -> Self {
+        let limit = std::env::var("LOOKUP_RATE_LIMIT")
+            .ok()
+            .and_then(|l| l.parse().ok())
+            .unwrap_or(60);
+        Self {
+            pool,
+            auth,
+            rate_limit: RateLimit {
+                limit,
+                windows: Arc::default(),
+            },
+        }
+    }
+
+    /// Check request has the lookup scope and is within the client's rate limit
+    async fn authorise(&self, headers: &HeaderMap, body: &[u8]) -> Result<String, Response> {
+        let client = self
+            .auth
+            .check(Scope::Lookup, headers, body, None)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        if let Err(retry_after) = self.rate_limit.check(&client) {
+            tracing::warn!("Rate limited lookup from {client}");
+            return Err((
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, retry_after.to_string())],
+                "Rate limit exceeded",
+            )
+                .into_response());
+        }
+        Ok(client)
+    }
+
+    /// Look up membership of Discord IDs, logging the access
+    async fn lookup(&self, client: &str, ids: &[i64]) -> Result<Vec<Lookup>, Error> {
+        let members = db::get_members_by_ids(&self.pool, ids)
+            .await?
+            .into_iter()
+            .map(|m| (m.discord_id, m.fresher))
+            .collect::<HashMap<_, _>>();
+        let lookups = ids
+            .iter()
+            .map(|&id| Lookup {
+                discord_id: id,
+                verified: members.contains_key(&id),
+                fresher: matches!(members.get(&id), Some(Fresher::YesPg | Fresher::YesUg)),
+            })
+            .collect::<Vec<_>>();
+        let log = lookups
+            .iter()
+            .map(|l| (l.discord_id, l.verified))
+            .collect::<Vec<_>>();
+        db::insert_lookup_log(&self.pool, client, &log).await?;
+        tracing::info!("{client} looked up {} members", ids.len());
+        Ok(lookups)
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn lookup_member(
+    lookups: Lookups,
+    Path(id): Path<i64>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let client = match lookups.authorise(&headers, &[]).await {
+        Ok(client) => client,
+        Err(res) => return res,
+    };
+    match lookups.lookup(&client, &[id]).await {
+        Ok(mut lookups) => Json(lookups.remove(0)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn lookup_members(
+    lookups: Lookups,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let client = match lookups.authorise(&headers, &body).await {
+        Ok(client) => client,
+        Err(res) => return res,
+    };
+    let Ok(BatchLookup { mut ids }) = serde_json::from_slice(&body) else {
+        return (StatusCode::BAD_REQUEST, "Invalid request body").into_response();
+    };
+    ids.sort_unstable();
+    ids.dedup();
+    if ids.len() > MAX_BATCH {
+        let msg = format!("At most {MAX_BATCH} IDs can be looked up at once");
+        return (StatusCode::BAD_REQUEST, msg).into_response();
+    }
+    match lookups.lookup(&client, &ids).await {
+        Ok(lookups) => Json(lookups).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{db, metrics, Data};
+use axum::{
+    extract::{MatchedPath, Request},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use poise::serenity_prelude as serenity;
+use std::sync::Arc;
+
+pub(crate) mod dashboard;
+pub(crate) use dashboard::*;
+
+pub(crate) mod dump;
+pub(crate) use dump::*;
+
+pub(crate) mod export;
+pub(crate) use export::*;
+
+pub(crate) mod import;
+pub(crate) use import::*;
+
+pub(crate) mod lookup;
+pub(crate) use lookup::*;
+
+pub(crate) mod ready;
+pub(crate) use ready::*;
+
+pub(crate) mod verify;
+pub(crate) use verify::*;
+
+pub(crate) fn router(
+    data: Data,
+    http: Arc<serenity::Http>,
+    shards: Arc<serenity::ShardManager>,
+) -> axum::Router {
+    let pool = data.db.clone();
+    let auth = data.auth.clone();
+
+    let dashboard = Dashboard::new(data.clone(), http);
+    let index_dashboard = dashboard.clone();
+    let index_handler = |headers, query| dashboard_index(index_dashboard, headers, query);
+    let login_dashboard = dashboard.clone();
+    let login_handler = |form| dashboard_login(login_dashboard, form);
+    let logout_dashboard = dashboard.clone();
+    let logout_handler = || dashboard_logout(logout_dashboard);
+    let review_handler = |path, headers, form| dashboard_review(dashboard, path, headers, form);
+
+    let export_pool = pool.clone();
+    let export_auth = auth.clone();
+    let export_handler = |headers, query| export(export_pool, export_auth, headers, query);
+
+    let import_pool = pool.clone();
+    let import_auth = auth.clone();
+    let import_config = data.config.clone();
+    let import_handler =
+        |headers, body| import(import_pool, import_auth, import_config, headers, body);
+
+    let lookups = Lookups::from_env(pool.clone(), auth.clone());
+    let lookup_batch = lookups.clone();
+    let lookup_handler = |path, headers| lookup_member(lookups, path, headers);
+    let lookup_batch_handler = |headers, body| lookup_members(lookup_batch, headers, body);
+
+    let metrics_pool = pool.clone();
+    let metrics_handler = || metrics(metrics_pool);
+
+    let ready_deps = Readiness {
+        pool: pool.clone(),
+        ea: data.ea.clone(),
+        shards,
+    };
+    let ready_handler = || ready(ready_deps);
+
+    let has_mock = data.login_state.has_mock();
+    let mock_pool = pool.clone();
+    let mock_state = data.login_state.clone();
+    let mock_handler = |query| mock_login(mock_pool, mock_state, query);
+
+    let verify_pool = pool;
+    let verify_auth = auth;
+    let verify_state = data.login_state;
+    let verify_handler =
+        |headers, body| verify(verify_pool, verify_auth, headers, body, verify_state);
+
+    let mut router = axum::Router::new()
+        .route("/dashboard", axum::routing::get(index_handler))
+        .route("/dashboard/login", axum::routing::post(login_handler))
+        .route("/dashboard/logout", axum::routing::post(logout_handler))
+        .route(
+            "/dashboard/manual/{id}/{action}",
+            axum::routing::post(review_handler),
+        )
+        .route("/export", axum::routing::get(export_handler))
+        .route("/import", axum::routing::post(import_handler))
+        .route("/members/{id}", axum::routing::get(lookup_handler))
+        .route("/members/lookup", axum::routing::post(lookup_batch_handler))
+        .route("/metrics", axum::routing::get(metrics_handler))
+        .route("/ready", axum::routing::get(ready_handler))
+        .route("/up", axum::routing::get(up))
+        .route("/verify", axum::routing::post(verify_handler));
+    if has_mock {
+        router = router.route("/mock/login", axum::routing::get(mock_handler));
+    }
+    router.route_layer(axum::middleware::from_fn(track))
+}
+
+/// Count requests by matched route and response status
+async fn track(path: MatchedPath, req: Request, next: Next) -> Response {
+    let res = next.run(req).await;
+    metrics::HTTP_REQUESTS
+        .with_label_values(&[path.as_str(), res.status().as_str()])
+        .inc();
+    res
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn metrics(pool: sqlx::SqlitePool) -> impl IntoResponse {
+    let queues = [
+        ("pending", db::count_pending(&pool).await),
+        ("manual", db::count_manual(&pool).await),
+        ("gaijin", db::count_gaijin(&pool).await),
+    ];
+    for (queue, size) in queues {
+        match size {
+            Ok(size) => metrics::QUEUE_SIZE.with_label_values(&[queue]).set(size),
+            Err(e) => tracing::error!("Failed to count {queue}: {e}"),
+        }
+    }
+    match metrics::gather() {
+        Ok(body) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+            body,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn up() -> impl IntoResponse {
+    (StatusCode::OK, "Nano is up!")
+}
@@ -0,0 +1,114 @@
+use crate::{db, ea, Error};
+use axum::{http::StatusCode, response::IntoResponse, Json};
+use poise::serenity_prelude as serenity;
+use std::{sync::Arc, time::Duration};
+
+/// Database checks give up after this long, so a locked database fails instead of hanging
+const DB_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Dependencies checked by `/ready`
+#[derive(Clone)]
+pub(crate) struct Readiness {
+    pub pool: sqlx::SqlitePool,
+    pub ea: Arc<ea::Client>,
+    pub shards: Arc<serenity::ShardManager>,
+}
+
+#[derive(serde::Serialize)]
+struct Check {
+    ok: bool,
+    error: Option<String>,
+}
+
+impl Check {
+    async fn run(check: impl std::future::Future<Output = Result<(), Error>>) -> Self {
+        let error = match tokio::time::timeout(DB_TIMEOUT, check).await {
+            Ok(Ok(())) => None,
+            Ok(Err(e)) => Some(e.to_string()),
+            Err(_) => Some("Timed out".to_string()),
+        };
+        Self {
+            ok: error.is_none(),
+            error,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct Shard {
+    id: u32,
+    stage: String,
+    latency_ms: Option<u128>,
+}
+
+#[derive(serde::Serialize)]
+struct Discord {
+    ok: bool,
+    shards: Vec<Shard>,
+}
+
+/// Informational only, the list is fetched on demand so the last download can be a day
+/// old and a failure does not stop verification with other methods
+#[derive(serde::Serialize)]
+struct Ea {
+    /// Last download succeeded, or the list has not been needed yet
+    ok: bool,
+    last_fetch: Option<ea::Fetch>,
+}
+
+#[derive(serde::Serialize)]
+struct Report {
+    ready: bool,
+    database: Check,
+    fuzzy: Check,
+    discord: Discord,
+    ea: Ea,
+}
+
+/// Check each dependency without calling external services, 503 if the database or
+/// Discord is not ready
+#[tracing::instrument(skip_all)]
+pub(crate) async fn ready(deps: Readiness) -> impl IntoResponse {
+    let database = Check::run(db::ping(&deps.pool)).await;
+    let fuzzy = Check::run(db::ping_fuzzy(&deps.pool)).await;
+
+    let runners = deps.shards.runners.lock().await;
+    let mut shards = runners
+        .iter()
+        .map(|(id, info)| Shard {
+            id: id.0,
+            stage: info.stage.to_string(),
+            latency_ms: info.latency.map(|l| l.as_millis()),
+        })
+        .collect::<Vec<_>>();
+    shards.sort_by_key(|s| s.id);
+    let discord = Discord {
+        ok: !runners.is_empty()
+            && runners
+                .values()
+                .all(|info| info.stage == serenity::ConnectionStage::Connected),
+        shards,
+    };
+    drop(runners);
+
+    let last_fetch = deps.ea.last_fetch();
+    let ea = Ea {
+        ok: last_fetch.as_ref().is_none_or(|f| f.error.is_none()),
+        last_fetch,
+    };
+
+    let ready = database.ok && fuzzy.ok && discord.ok;
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let report = Report {
+        ready,
+        database,
+        fuzzy,
+        discord,
+        ea,
+    };
+    (status, Json(report))
+}
@@ -0,0 +1,330 @@
+use crate::{
+    auth::{Auth, Scope},
+    db, metrics, state, Actor, PendingMember,
+};
+use axum::{
+    body::Bytes,
+    extract::Query,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+#[derive(serde::Deserialize)]
+pub(crate) struct Verify {
+    /// State token from the login link, carrying the Discord ID
+    state: String,
+    shortcode: String,
+    fullname: String,
+    key: Option<String>,
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn verify(
+    pool: sqlx::SqlitePool,
+    auth: Auth,
+    headers: HeaderMap,
+    body: Bytes,
+    login_state: state::LoginState,
+) -> impl IntoResponse {
+    let Ok(verify) = serde_json::from_slice::<Verify>(&body) else {
+        return (StatusCode::BAD_REQUEST, "Invalid request body").into_response();
+    };
+    let client = match auth
+        .check(Scope::Verify, &headers, &body, verify.key.as_deref())
+        .await
+    {
+        Ok(client) => client,
+        Err(status) => return (status, "Auth required").into_response(),
+    };
+    if let Some(provider) = login_state.provider_of(&verify.state) {
+        if provider.key != client {
+            tracing::warn!("{client} tried to complete a {} login", provider.name);
+            metrics::failed("wrong_provider");
+            let msg = "Key cannot complete logins for this provider";
+            return (StatusCode::FORBIDDEN, msg).into_response();
+        }
+    }
+
+    complete_login(
+        &pool,
+        &login_state,
+        Actor::Route("/verify"),
+        &verify.state,
+        &verify.shortcode,
+        &verify.fullname,
+    )
+    .await
+}
+
+#[derive(serde::Deserialize)]
+pub(crate) struct MockLogin {
+    state: String,
+    shortcode: Option<String>,
+    fullname: Option<String>,
+}
+
+/// Stand-in for an external login provider, completing login for a mock provider's state
+/// token without any credentials, only routed if a mock provider is configured
+#[tracing::instrument(skip_all)]
+pub(crate) async fn mock_login(
+    pool: sqlx::SqlitePool,
+    login_state: state::LoginState,
+    Query(login): Query<MockLogin>,
+) -> impl IntoResponse {
+    if !login_state
+        .provider_of(&login.state)
+        .is_some_and(|p| p.mock)
+    {
+        return (StatusCode::FORBIDDEN, "Not a mock login provider").into_response();
+    }
+    let shortcode = login.shortcode.unwrap_or_else(|| "mock1234".to_string());
+    let fullname = login.fullname.unwrap_or_else(|| "Mock User".to_string());
+    let actor = Actor::Route("/mock/login");
+    complete_login(
+        &pool,
+        &login_state,
+        actor,
+        &login.state,
+        &shortcode,
+        &fullname,
+    )
+    .await
+}
+
+/// Check state token from login provider and add user to pending, ready for `login_2`
+async fn complete_login(
+    pool: &sqlx::SqlitePool,
+    login_state: &state::LoginState,
+    actor: Actor<'_>,
+    token: &str,
+    shortcode: &str,
+    fullname: &str,
+) -> Response {
+    let (id, provider) = match login_state.consume(pool, token).await {
+        Ok(Ok(login)) => login,
+        Ok(Err(e)) => {
+            tracing::warn!("Rejected login for {shortcode}: {e}");
+            let (status, reason) = match e {
+                state::Rejected::Invalid => (StatusCode::FORBIDDEN, "state_invalid"),
+                state::Rejected::Expired => (StatusCode::GONE, "state_expired"),
+                state::Rejected::Replayed => (StatusCode::CONFLICT, "state_replayed"),
+            };
+            metrics::failed(reason);
+            return (status, e.to_string()).into_response();
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    };
+
+    // Delete from pending if exists
+    let _ = db::delete_pending_by_id(pool, actor, id).await;
+
+    match db::insert_pending(
+        pool,
+        actor,
+        PendingMember {
+            discord_id: id,
+            shortcode: shortcode.to_string(),
+            realname: fullname.to_string(),
+        },
+    )
+    .await
+    {
+        Ok(()) => {
+            tracing::info!(
+                "ID {id} added via {}: {shortcode}, {fullname}",
+                provider.name
+            );
+            (StatusCode::OK, "Member added to `pending` database").into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auth, db::test_pool, state::Provider, verify::verify_pending, Fresher, Method};
+    use poise::serenity_prelude::UserId;
+
+    fn provider(name: &str, url: &str, users: Vec<i64>, mock: bool) -> Provider {
+        Provider {
+            name: name.to_string(),
+            url: url.to_string(),
+            secret: format!("{name} secret"),
+            key: name.to_string(),
+            users,
+            mock,
+        }
+    }
+
+    fn state_of(link: &str) -> String {
+        url::Url::parse(link)
+            .unwrap()
+            .query_pairs()
+            .find(|(k, _)| k == "state")
+            .unwrap()
+            .1
+            .into_owned()
+    }
+
+    /// Login provider backend, calling `/verify` with a request signed by its API key
+    struct Backend {
+        pool: sqlx::SqlitePool,
+        auth: Auth,
+        name: &'static str,
+        secret: String,
+        login_state: state::LoginState,
+    }
+
+    impl Backend {
+        async fn new(
+            pool: &sqlx::SqlitePool,
+            login_state: &state::LoginState,
+            name: &'static str,
+        ) -> Self {
+            let hash = auth::hash_key(&auth::generate_key());
+            db::insert_api_key(pool, Actor::System, name, &hash, "verify")
+                .await
+                .unwrap();
+            let auth = auth::test_auth(pool.clone());
+            Self {
+                pool: pool.clone(),
+                name,
+                secret: auth.signing_secret(&hash),
+                auth,
+                login_state: login_state.clone(),
+            }
+        }
+
+        async fn verify(&self, state: &str, fullname: &str, secret: &str) -> StatusCode {
+            let body = serde_json::json!({
+                "state": state,
+                "shortcode": "ab123",
+                "fullname": fullname,
+            })
+            .to_string();
+            let headers = auth::signed_headers(self.name, secret, body.as_bytes());
+            verify(
+                self.pool.clone(),
+                self.auth.clone(),
+                headers,
+                Bytes::from(body),
+                self.login_state.clone(),
+            )
+            .await
+            .into_response()
+            .status()
+        }
+    }
+
+    async fn mock(pool: &sqlx::SqlitePool, ls: &state::LoginState, state: &str) -> StatusCode {
+        let login = MockLogin {
+            state: state.to_string(),
+            shortcode: None,
+            fullname: None,
+        };
+        mock_login(pool.clone(), ls.clone(), Query(login))
+            .await
+            .into_response()
+            .status()
+    }
+
+    fn login_state() -> state::LoginState {
+        state::LoginState::new(
+            vec![
+                provider(
+                    "imperial",
+                    "https://login.test/verify?state={state}",
+                    vec![],
+                    false,
+                ),
+                provider(
+                    "mock",
+                    "http://localhost/mock/login?state={state}",
+                    vec![1],
+                    true,
+                ),
+            ],
+            60,
+        )
+    }
+
+    #[tokio::test]
+    async fn provider_login_completes_through_verify() {
+        let pool = test_pool().await;
+        let ls = login_state();
+        let backend = Backend::new(&pool, &ls, "imperial").await;
+        let staging = Backend::new(&pool, &ls, "staging").await;
+
+        // login_1 gives the user the provider's link, and the provider calls /verify
+        let link = ls.login_link(&pool, 2).await.unwrap();
+        assert!(link.starts_with("https://login.test/verify"));
+        let state = state_of(&link);
+        // Other providers' keys can't complete its logins
+        assert_eq!(
+            staging.verify(&state, "Real Name", &staging.secret).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            backend.verify(&state, "Real Name", "wrong secret").await,
+            StatusCode::UNAUTHORIZED
+        );
+        let secret = &backend.secret;
+        assert_eq!(
+            backend.verify(&state, "Real Name", secret).await,
+            StatusCode::OK
+        );
+        // Replaying the signed request is rejected before the state is looked at, and a
+        // freshly signed retry finds the state already used
+        assert_eq!(
+            backend.verify(&state, "Real Name", secret).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            backend.verify(&state, "Other Name", secret).await,
+            StatusCode::CONFLICT
+        );
+
+        // login_2 finds the pending entry, and login_6 verifies from it
+        assert!(db::get_pending_by_id(&pool, 2).await.unwrap().is_some());
+        let member = verify_pending(&pool, UserId::new(2), "Nick", Fresher::No)
+            .await
+            .unwrap();
+        assert_eq!(member.realname, "Real Name");
+        assert!(matches!(member.method, Method::Login));
+        assert!(db::get_pending_by_id(&pool, 2).await.unwrap().is_none());
+
+        // The mock route cannot complete a real provider's login
+        let link = ls.login_link(&pool, 3).await.unwrap();
+        assert_eq!(
+            mock(&pool, &ls, &state_of(&link)).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn mock_provider_completes_login() {
+        let pool = test_pool().await;
+        let ls = login_state();
+
+        // login_1 gives test users the mock provider's link
+        let link = ls.login_link(&pool, 1).await.unwrap();
+        assert!(link.starts_with("http://localhost/mock/login"));
+        let state = state_of(&link);
+        assert_eq!(mock(&pool, &ls, &state).await, StatusCode::OK);
+        assert_eq!(mock(&pool, &ls, &state).await, StatusCode::CONFLICT);
+
+        let member = verify_pending(&pool, UserId::new(1), "Mock", Fresher::No)
+            .await
+            .unwrap();
+        assert_eq!(member.realname, "Mock User");
+
+        // Tokens are only valid for the provider that signed them
+        let link = ls.login_link(&pool, 2).await.unwrap();
+        let forged = state_of(&link).replacen("imperial", "mock", 1);
+        assert!(matches!(
+            ls.consume(&pool, &forged).await.unwrap(),
+            Err(state::Rejected::Invalid)
+        ));
+    }
+}
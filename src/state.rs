@@ -0,0 +1,242 @@
+use crate::{db, var, Error};
+use anyhow::Context as _;
+use hmac::{Hmac, Mac};
+use poise::serenity_prelude as serenity;
+use sha2::Sha256;
+
+/// Reason a login state token was rejected
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Rejected {
+    /// Malformed, bad signature or not issued by Nano
+    Invalid,
+    /// Past its expiry time
+    Expired,
+    /// Already used to verify an account
+    Replayed,
+}
+
+impl std::fmt::Display for Rejected {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Rejected::Invalid => write!(f, "Invalid state token"),
+            Rejected::Expired => write!(f, "State token expired, please restart verification"),
+            Rejected::Replayed => write!(f, "State token already used"),
+        }
+    }
+}
+
+/// Login link used for the `imperial` provider if `LOGIN_PROVIDER_IMPERIAL_URL` is not set
+const IMPERIAL_URL: &str = "https://icas.8bitsqu.id/verify?state={state}";
+
+/// External service that checks a user's Imperial Login, then posts their details to
+/// `/verify` along with the state token from its login link
+#[derive(Clone, Debug)]
+pub(crate) struct Provider {
+    pub(crate) name: String,
+    /// Login link, with `{state}` replaced by the state token
+    pub(crate) url: String,
+    /// Secret for signing state tokens issued for this provider
+    pub(crate) secret: String,
+    /// Name of the API key the provider calls `/verify` with, only it can complete logins
+    /// with this provider's state tokens
+    pub(crate) key: String,
+    /// Discord IDs allowed to use this provider, or everyone if empty
+    pub(crate) users: Vec<i64>,
+    /// Logins are completed by Nano's own `/mock/login` route, without any credentials
+    pub(crate) mock: bool,
+}
+
+impl Provider {
+    /// Read provider `name` from `LOGIN_PROVIDER_<NAME>_URL`, `_SECRET`, `_KEY`, `_USERS`
+    /// and `_MOCK`
+    fn from_env(name: &str) -> Result<Self, Error> {
+        let var = |key: &str| {
+            let var = format!(
+                "LOGIN_PROVIDER_{}_{key}",
+                name.to_uppercase().replace('-', "_")
+            );
+            std::env::var(&var)
+                .ok()
+                .filter(|v| !v.is_empty())
+                .ok_or(var)
+        };
+        let imperial = name == "imperial";
+        let url = match var("URL") {
+            Err(_) if imperial => IMPERIAL_URL.to_string(),
+            url => url.map_err(|var| format!("{var} not found"))?,
+        };
+        let secret = match var("SECRET") {
+            Err(_) if imperial => var!("LOGIN_STATE_SECRET"),
+            secret => secret.map_err(|var| format!("{var} not found"))?,
+        };
+        let key = var("KEY").unwrap_or_else(|_| name.to_string());
+        let users = var("USERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                id.parse()
+                    .context(format!("Invalid user ID {id} for {name}"))
+            })
+            .collect::<Result<Vec<i64>, _>>()?;
+        let mock = var("MOCK").is_ok_and(|m| m == "true");
+
+        if !url.contains("{state}") {
+            return Err(format!("Login URL for {name} has no {{state}} placeholder").into());
+        }
+        url::Url::parse(&url.replace("{state}", "state"))
+            .context(format!("Login URL for {name} is invalid"))?;
+        if mock {
+            if users.is_empty() {
+                return Err(
+                    format!("Mock login provider {name} must be limited to test users").into(),
+                );
+            }
+            tracing::warn!("Mock login provider {name} enabled for {users:?}");
+        }
+        Ok(Self {
+            name: name.to_string(),
+            url,
+            secret,
+            key,
+            users,
+            mock,
+        })
+    }
+
+    /// Whether Discord user can log in with this provider
+    pub(crate) fn allows(&self, id: i64) -> bool {
+        self.users.is_empty() || self.users.contains(&id)
+    }
+}
+
+/// Issues and checks signed, expiring, single-use state tokens for the Imperial Login flow
+///
+/// Tokens are `<provider>.<discord id>.<expiry>.<nonce>.<signature>`, signed with the
+/// provider's secret, with each nonce stored in the `login_states` table so it can only be
+/// used once
+#[derive(Clone)]
+pub(crate) struct LoginState {
+    providers: Vec<Provider>,
+    /// Seconds a token is valid for
+    ttl: i64,
+}
+
+impl LoginState {
+    pub(crate) fn new(providers: Vec<Provider>, ttl: i64) -> Self {
+        Self { providers, ttl }
+    }
+
+    /// Read providers named in `LOGIN_PROVIDERS`, by default just `imperial`
+    pub(crate) fn from_env() -> Result<Self, Error> {
+        let names = std::env::var("LOGIN_PROVIDERS").unwrap_or_else(|_| "imperial".to_string());
+        let mut providers = Vec::new();
+        for name in names.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let name = name.to_lowercase();
+            if !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err(format!("Login provider name {name} must be alphanumeric").into());
+            }
+            providers.push(Provider::from_env(&name)?);
+        }
+        if providers.iter().all(|p| !p.users.is_empty()) {
+            return Err("LOGIN_PROVIDERS needs a provider open to all users".into());
+        }
+        let ttl = 60
+            * std::env::var("LOGIN_STATE_MINS")
+                .ok()
+                .and_then(|m| m.parse().ok())
+                .unwrap_or(30);
+        Ok(Self::new(providers, ttl))
+    }
+
+    /// Whether any provider is completed by the `/mock/login` route
+    pub(crate) fn has_mock(&self) -> bool {
+        self.providers.iter().any(|p| p.mock)
+    }
+
+    /// Provider for Discord user, preferring one they are specifically listed for
+    fn provider_for(&self, id: i64) -> Option<&Provider> {
+        self.providers
+            .iter()
+            .find(|p| !p.users.is_empty() && p.allows(id))
+            .or_else(|| self.providers.iter().find(|p| p.users.is_empty()))
+    }
+
+    /// Provider named in token, without checking the token
+    pub(crate) fn provider_of(&self, token: &str) -> Option<&Provider> {
+        let (name, _) = token.split_once('.')?;
+        self.providers.iter().find(|p| p.name == name)
+    }
+
+    fn mac(provider: &Provider, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(provider.secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    /// Issue a new token for a Discord ID and return the login link of their provider
+    pub(crate) async fn login_link(
+        &self,
+        pool: &sqlx::SqlitePool,
+        id: i64,
+    ) -> Result<String, Error> {
+        let provider = self
+            .provider_for(id)
+            .context(format!("No login provider for {id}"))?;
+        let nonce = hex::encode(rand::random::<[u8; 16]>());
+        let expires_at = db::insert_login_state(pool, &nonce, id, self.ttl).await?;
+        let payload = format!("{}.{id}.{expires_at}.{nonce}", provider.name);
+        let signature = hex::encode(Self::mac(provider, &payload).finalize().into_bytes());
+        Ok(provider
+            .url
+            .replace("{state}", &format!("{payload}.{signature}")))
+    }
+
+    /// Check token and mark it as used, returning the Discord ID it was issued for and
+    /// its provider
+    pub(crate) async fn consume(
+        &self,
+        pool: &sqlx::SqlitePool,
+        token: &str,
+    ) -> Result<Result<(i64, &Provider), Rejected>, Error> {
+        let Some(provider) = self.provider_of(token) else {
+            return Ok(Err(Rejected::Invalid));
+        };
+        let Some((payload, signature)) = token.rsplit_once('.') else {
+            return Ok(Err(Rejected::Invalid));
+        };
+        let Ok(signature) = hex::decode(signature) else {
+            return Ok(Err(Rejected::Invalid));
+        };
+        if Self::mac(provider, payload)
+            .verify_slice(&signature)
+            .is_err()
+        {
+            return Ok(Err(Rejected::Invalid));
+        }
+        let mut parts = payload.splitn(4, '.').skip(1);
+        let (Some(Ok(id)), Some(Ok(expires_at)), Some(nonce)) = (
+            parts.next().map(str::parse::<i64>),
+            parts.next().map(str::parse::<i64>),
+            parts.next(),
+        ) else {
+            return Ok(Err(Rejected::Invalid));
+        };
+        if !provider.allows(id) {
+            return Ok(Err(Rejected::Invalid));
+        }
+        if expires_at < serenity::Timestamp::now().unix_timestamp() {
+            return Ok(Err(Rejected::Expired));
+        }
+        Ok(match db::use_login_state(pool, nonce, id).await? {
+            None => Err(Rejected::Invalid),
+            Some(false) => Err(Rejected::Replayed),
+            Some(true) => Ok((id, provider)),
+        })
+    }
+}
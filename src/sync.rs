@@ -0,0 +1,338 @@
+use crate::{academic_year, config, db, ea, mentions, Actor, Error, Member};
+use poise::serenity_prelude::{self as serenity, CreateEmbed, CreateMessage};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+/// Action taken for lapsed members missing from the EA membership list
+#[derive(Copy, Clone, Debug)]
+pub(crate) enum Policy {
+    /// Only list missing members in the sync report
+    Flag,
+    /// Also move missing members from the member role to the old member role
+    Demote,
+}
+
+impl std::str::FromStr for Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "flag" => Ok(Self::Flag),
+            "demote" => Ok(Self::Demote),
+            _ => Err(format!("Unknown EA sync policy: {s}")),
+        }
+    }
+}
+
+impl std::fmt::Display for Policy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Policy::Flag => write!(f, "Flag"),
+            Policy::Demote => write!(f, "Demote"),
+        }
+    }
+}
+
+/// Periodic sync of the members table against the EA membership list
+pub(crate) struct EaSync {
+    pub(crate) config: config::Config,
+    pub(crate) db: sqlx::SqlitePool,
+    pub(crate) ea: Arc<ea::Client>,
+    pub(crate) interval: Duration,
+    pub(crate) policy: Policy,
+}
+
+/// Lapsed members split by whether they are in the EA membership list
+pub(crate) struct Outcome {
+    pub(crate) year: i64,
+    /// Found in the EA list and renewed for `year`
+    pub(crate) renewed: Vec<Member>,
+    /// Not in the EA list
+    pub(crate) missing: Vec<Member>,
+}
+
+impl Outcome {
+    /// Drop missing members already handled by an earlier sync, so each is only reported
+    /// and demoted once, returning the IDs of everyone missing now
+    pub(crate) fn retain_unhandled(&mut self, handled: &HashSet<i64>) -> HashSet<i64> {
+        let missing = self.missing.iter().map(|m| m.discord_id).collect();
+        self.missing.retain(|m| !handled.contains(&m.discord_id));
+        missing
+    }
+
+    /// Members whose roles are changed under `policy`
+    pub(crate) fn demoted(&self, policy: Policy) -> &[Member] {
+        match policy {
+            Policy::Flag => &[],
+            Policy::Demote => &self.missing,
+        }
+    }
+}
+
+/// Renew lapsed members found in the EA list, returning who was renewed and who is missing
+pub(crate) async fn reconcile(pool: &sqlx::SqlitePool, ea: &ea::Client) -> Result<Outcome, Error> {
+    let members = ea.members(true).await?;
+    if members.list.is_empty() {
+        return Err("EA membership list is empty, skipping sync".into());
+    }
+    let paid = members
+        .list
+        .iter()
+        .map(ea::Member::key)
+        .collect::<HashSet<_>>();
+
+    let year = db::current_academic_year(pool).await?;
+    let (renewed, missing): (Vec<_>, Vec<_>) = db::get_lapsed_members(pool, year)
+        .await?
+        .into_iter()
+        .partition(|m| paid.contains(&m.shortcode.to_lowercase()));
+
+    for m in &renewed {
+        db::renew_member(pool, Actor::System, m.discord_id).await?;
+    }
+    Ok(Outcome {
+        year,
+        renewed,
+        missing,
+    })
+}
+
+impl EaSync {
+    /// Run sync now and then once every interval
+    pub(crate) async fn run_periodically(self, http: Arc<serenity::Http>) {
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut handled = HashSet::new();
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.run(&http, &mut handled).await {
+                tracing::error!("EA sync failed: {e}");
+            }
+        }
+    }
+
+    /// Renew lapsed members found in the EA list and restore their member role, handle
+    /// the rest according to policy and send a report to the added users channel
+    ///
+    /// Missing members in `handled` were reported by an earlier run and are skipped, it
+    /// is updated to everyone missing now except those whose roles failed to change
+    #[tracing::instrument(skip_all)]
+    pub(crate) async fn run(
+        &self,
+        http: &serenity::Http,
+        handled: &mut HashSet<i64>,
+    ) -> Result<(), Error> {
+        let mut outcome = reconcile(&self.db, &self.ea).await?;
+        let mut missing_now = outcome.retain_unhandled(handled);
+        let guild = self.config.get();
+        let mut failed = Vec::new();
+        let changes = outcome
+            .renewed
+            .iter()
+            .map(|m| (m, guild.old_member, guild.member))
+            .chain(
+                outcome
+                    .demoted(self.policy)
+                    .iter()
+                    .map(|m| (m, guild.member, guild.old_member)),
+            );
+        for (m, from, to) in changes {
+            if let Err(e) = swap_role(http, guild.server, m.discord_id, from, to).await {
+                tracing::warn!("EA sync failed to change roles of {}: {e}", m.discord_id);
+                missing_now.remove(&m.discord_id);
+                failed.push(m.clone());
+            }
+        }
+        *handled = missing_now;
+
+        let Outcome {
+            year,
+            renewed,
+            missing,
+        } = outcome;
+        tracing::info!(
+            "EA sync for {}: {} renewed, {} newly missing ({}), {} role changes failed",
+            academic_year(year),
+            renewed.len(),
+            missing.len(),
+            self.policy,
+            failed.len()
+        );
+        if renewed.is_empty() && missing.is_empty() {
+            return Ok(());
+        }
+
+        let mut embed = CreateEmbed::new()
+            .title(format!("EA membership sync for {}", academic_year(year)))
+            .field(
+                format!("Renewed ({})", renewed.len()),
+                mentions(&renewed),
+                false,
+            )
+            .field(
+                format!("Newly not in EA list ({})", missing.len()),
+                mentions(&missing),
+                false,
+            )
+            .field("Policy", self.policy.to_string(), true)
+            .timestamp(serenity::Timestamp::now());
+        if !failed.is_empty() {
+            embed = embed.field(
+                format!("Role changes failed ({})", failed.len()),
+                mentions(&failed),
+                false,
+            );
+        }
+        guild
+            .au_ch_id
+            .send_message(http, CreateMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Move user from one role to another
+async fn swap_role(
+    http: &serenity::Http,
+    server: serenity::GuildId,
+    id: i64,
+    from: serenity::RoleId,
+    to: serenity::RoleId,
+) -> Result<(), serenity::Error> {
+    let user = serenity::UserId::new(id.cast_unsigned());
+    http.add_member_role(server, user, to, None).await?;
+    http.remove_member_role(server, user, from, None).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_pool, Fresher, Method};
+
+    /// Serve `members` as the EA membership list on a local port
+    async fn mock_ea(members: serde_json::Value) -> ea::Client {
+        let app = axum::Router::new().route(
+            "/members",
+            axum::routing::get(move || async move { axum::Json(members) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        ea::Client::new(
+            "key".to_string(),
+            format!("http://{addr}/members"),
+            Duration::from_mins(5),
+        )
+        .unwrap()
+    }
+
+    fn ea_member(login: &str, cid: &str) -> serde_json::Value {
+        serde_json::json!({
+            "FirstName": "First",
+            "Surname": "Last",
+            "CID": cid,
+            "Login": login,
+            "OrderNo": 1,
+        })
+    }
+
+    /// Insert member without a membership year, so they are lapsed
+    async fn lapsed(pool: &sqlx::SqlitePool, id: i64, shortcode: &str) {
+        let mut tx = pool.begin().await.unwrap();
+        db::insert_member_tx(
+            &mut tx,
+            Actor::System,
+            Member {
+                discord_id: id,
+                shortcode: shortcode.to_string(),
+                nickname: format!("Nick {id}"),
+                realname: format!("Name {id}"),
+                fresher: Fresher::No,
+                verified_at: None,
+                method: Method::Manual,
+                verified_by: None,
+            },
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+    }
+
+    fn ids(members: &[Member]) -> Vec<i64> {
+        members.iter().map(|m| m.discord_id).collect()
+    }
+
+    #[tokio::test]
+    async fn sync_renews_listed_and_handles_missing_by_policy() {
+        let ea = mock_ea(serde_json::json!([
+            ea_member("AB123", "00000001"),
+            ea_member("", "00000002"),
+        ]))
+        .await;
+        for policy in [Policy::Flag, Policy::Demote] {
+            let pool = test_pool().await;
+            lapsed(&pool, 1, "ab123").await;
+            lapsed(&pool, 2, "00000002").await;
+            lapsed(&pool, 3, "zz999").await;
+            db::insert_member(
+                &pool,
+                Actor::System,
+                Member {
+                    discord_id: 4,
+                    shortcode: "cd456".to_string(),
+                    nickname: "Current".to_string(),
+                    realname: "Current Name".to_string(),
+                    fresher: Fresher::No,
+                    verified_at: None,
+                    method: Method::Manual,
+                    verified_by: None,
+                },
+            )
+            .await
+            .unwrap();
+
+            let outcome = reconcile(&pool, &ea).await.unwrap();
+            assert_eq!(ids(&outcome.renewed), [1, 2], "{policy}");
+            assert_eq!(ids(&outcome.missing), [3], "{policy}");
+            let demoted: &[i64] = match policy {
+                Policy::Flag => &[],
+                Policy::Demote => &[3],
+            };
+            assert_eq!(ids(outcome.demoted(policy)), demoted, "{policy}");
+
+            let lapsed = db::get_lapsed_members(&pool, outcome.year).await.unwrap();
+            assert_eq!(ids(&lapsed), [3], "{policy}");
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_members_are_only_handled_once() {
+        let ea = mock_ea(serde_json::json!([ea_member("AB123", "00000001")])).await;
+        let pool = test_pool().await;
+        lapsed(&pool, 1, "ab123").await;
+        lapsed(&pool, 3, "zz999").await;
+
+        let mut handled = HashSet::new();
+        let mut outcome = reconcile(&pool, &ea).await.unwrap();
+        handled = outcome.retain_unhandled(&handled);
+        assert_eq!(ids(&outcome.renewed), [1]);
+        assert_eq!(ids(&outcome.missing), [3]);
+
+        // The next run has nothing renewed and nobody newly missing
+        lapsed(&pool, 5, "yy888").await;
+        let mut outcome = reconcile(&pool, &ea).await.unwrap();
+        handled = outcome.retain_unhandled(&handled);
+        assert!(outcome.renewed.is_empty());
+        assert_eq!(ids(&outcome.missing), [5]);
+        assert_eq!(handled, HashSet::from([3, 5]));
+    }
+
+    #[tokio::test]
+    async fn sync_skips_empty_list() {
+        let ea = mock_ea(serde_json::json!([])).await;
+        let pool = test_pool().await;
+        lapsed(&pool, 1, "ab123").await;
+        assert!(reconcile(&pool, &ea).await.is_err());
+        let year = db::current_academic_year(&pool).await.unwrap();
+        assert_eq!(db::get_lapsed_members(&pool, year).await.unwrap().len(), 1);
+    }
+}
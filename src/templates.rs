@@ -0,0 +1,174 @@
+use crate::{
+    db,
+    locale::{Locale, Msg},
+    Data, Error, Fresher,
+};
+use poise::{serenity_prelude as serenity, ChoiceParameter};
+
+/// User-facing message in the verification flow, editable per locale with `/template`
+///
+/// Templates can include placeholders: `{user}` mentions the user, `{contact}` mentions
+/// the committee role and `{fresher}` is "freshers" or "postgraduate freshers" in the
+/// fresher welcome message
+#[derive(Copy, Clone, Debug, ChoiceParameter)]
+pub(crate) enum Template {
+    #[name = "More info"]
+    Info,
+    #[name = "Start verification"]
+    Start,
+    #[name = "Imperial Login intro"]
+    LoginIntro,
+    #[name = "Imperial Login form"]
+    LoginForm,
+    #[name = "Membership intro"]
+    MembershipIntro,
+    #[name = "Manual intro"]
+    ManualIntro,
+    #[name = "Welcome"]
+    Welcome,
+    #[name = "Welcome fresher"]
+    WelcomeFresher,
+}
+
+impl Template {
+    pub(crate) const ALL: [Template; 8] = [
+        Template::Info,
+        Template::Start,
+        Template::LoginIntro,
+        Template::LoginForm,
+        Template::MembershipIntro,
+        Template::ManualIntro,
+        Template::Welcome,
+        Template::WelcomeFresher,
+    ];
+
+    /// Key in the templates table
+    pub(crate) fn key(self) -> &'static str {
+        match self {
+            Template::Info => "info",
+            Template::Start => "start",
+            Template::LoginIntro => "login_intro",
+            Template::LoginForm => "login_form",
+            Template::MembershipIntro => "membership_intro",
+            Template::ManualIntro => "manual_intro",
+            Template::Welcome => "welcome",
+            Template::WelcomeFresher => "welcome_fresher",
+        }
+    }
+
+    /// Template and locale of a key in the templates table
+    pub(crate) fn from_locale_key(key: &str) -> Option<(Self, Locale)> {
+        Self::ALL.into_iter().find_map(|t| {
+            Locale::ALL
+                .into_iter()
+                .find(|&l| t.locale_key(l) == key)
+                .map(|l| (t, l))
+        })
+    }
+
+    /// Key in the templates table for locale, English uses the bare key
+    pub(crate) fn locale_key(self, locale: Locale) -> String {
+        match locale {
+            Locale::En => self.key().to_string(),
+            Locale::ZhCn => format!("{}.{}", self.key(), locale.code()),
+        }
+    }
+
+    fn msg(self) -> Msg {
+        match self {
+            Template::Info => Msg::InfoTemplate,
+            Template::Start => Msg::StartTemplate,
+            Template::LoginIntro => Msg::LoginIntroTemplate,
+            Template::LoginForm => Msg::LoginFormTemplate,
+            Template::MembershipIntro => Msg::MembershipIntroTemplate,
+            Template::ManualIntro => Msg::ManualIntroTemplate,
+            Template::Welcome => Msg::WelcomeTemplate,
+            Template::WelcomeFresher => Msg::WelcomeFresherTemplate,
+        }
+    }
+
+    /// Placeholders allowed in template, without braces
+    pub(crate) fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            Template::WelcomeFresher => &["user", "contact", "fresher"],
+            _ => &["user", "contact"],
+        }
+    }
+
+    /// Check body is closed and only uses the template's placeholders
+    pub(crate) fn validate(self, body: &str) -> Result<(), String> {
+        let mut rest = body;
+        while let Some(start) = rest.find(['{', '}']) {
+            let tail = &rest[start..];
+            let len = match tail.find('}') {
+                Some(len) if tail.starts_with('{') => len,
+                _ => return Err("Unmatched brace, placeholders are written as `{name}`".into()),
+            };
+            let name = &rest[start + 1..start + len];
+            if !self.placeholders().contains(&name) {
+                let available = self
+                    .placeholders()
+                    .iter()
+                    .map(|p| format!("`{{{p}}}`"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(format!(
+                    "Unknown placeholder `{{{name}}}`, available: {available}"
+                ));
+            }
+            rest = &rest[start + len + 1..];
+        }
+        Ok(())
+    }
+
+    /// Edited body of template in locale, or its default if not edited
+    pub(crate) async fn body(
+        self,
+        pool: &sqlx::SqlitePool,
+        locale: Locale,
+    ) -> Result<String, Error> {
+        Ok(db::get_template(pool, &self.locale_key(locale))
+            .await?
+            .map_or_else(|| locale.t(self.msg()).to_string(), |t| t.body))
+    }
+
+    /// Fill placeholders in body for user
+    pub(crate) fn fill(data: &Data, body: &str, user: &serenity::User, fresher: &str) -> String {
+        body.replace("{user}", &user.to_string())
+            .replace("{contact}", &format!("<@&{}>", data.committee))
+            .replace("{fresher}", fresher)
+    }
+
+    /// Text of template for user in locale
+    pub(crate) async fn render(
+        self,
+        data: &Data,
+        user: &serenity::User,
+        locale: Locale,
+    ) -> Result<String, Error> {
+        Ok(Self::fill(
+            data,
+            &self.body(&data.db, locale).await?,
+            user,
+            "",
+        ))
+    }
+}
+
+/// Welcome message for a newly verified member
+pub(crate) async fn welcome(
+    data: &Data,
+    user: &serenity::User,
+    fresher: Fresher,
+    locale: Locale,
+) -> Result<String, Error> {
+    let (template, fresher) = match (fresher, locale) {
+        (Fresher::No, _) => (Template::Welcome, ""),
+        (Fresher::YesPg, Locale::En) => (Template::WelcomeFresher, "postgraduate freshers"),
+        (Fresher::YesUg, Locale::En) => (Template::WelcomeFresher, "freshers"),
+        (Fresher::YesPg, Locale::ZhCn) => (Template::WelcomeFresher, "研究生新生"),
+        (Fresher::YesUg, Locale::ZhCn) => (Template::WelcomeFresher, "新生"),
+    };
+    let body = template.body(&data.db, locale).await?;
+    Ok(Template::fill(data, &body, user, fresher))
+}
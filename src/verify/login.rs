@@ -1,41 +1,43 @@
-use crate::{db, verify, Data, Error, Fresher};
+use crate::{
+    db,
+    locale::{Locale, Msg},
+    metrics,
+    templates::Template,
+    verify, Actor, Data, Error, Fresher, Member, Method,
+};
 use poise::serenity_prelude::{
     self as serenity, CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse,
     CreateInteractionResponseMessage, CreateMessage,
 };
 use poise::Modal;
 
-const LOGIN_INTRO: &str = indoc::indoc! {"
-    To use automatic verification via Imperial Login:
-    - Open the link provided and login using your shortcode
-    - Your account will be checked and then the login details immediately discarded
-    - Your shortcode will then be connected to your Discord Account by Nano
-
-    You can then complete the remaining details in the next step!
-"};
-
 #[tracing::instrument(skip_all)]
 pub(crate) async fn login_1(
     ctx: &serenity::Context,
     m: &serenity::ComponentInteraction,
+    data: &Data,
 ) -> Result<(), Error> {
-    let verify_url = format!("https://icas.8bitsqu.id/verify?id={}", m.user.id.get());
+    let locale = Locale::from_discord(&m.locale);
+    let verify_url = data
+        .login_state
+        .login_link(&data.db, m.user.id.into())
+        .await?;
     m.create_response(
         &ctx.http,
         CreateInteractionResponse::UpdateMessage(
             CreateInteractionResponseMessage::new()
-                .content(LOGIN_INTRO)
+                .content(Template::LoginIntro.render(data, &m.user, locale).await?)
                 .components(vec![CreateActionRow::Buttons(vec![
                     CreateButton::new("restart")
                         .style(serenity::ButtonStyle::Danger)
                         .emoji('🔙'),
                     CreateButton::new_link(verify_url)
                         .emoji('🚀')
-                        .label("Login Here"),
+                        .label(locale.t(Msg::LoginHereButton)),
                     CreateButton::new("login_2")
                         .style(serenity::ButtonStyle::Secondary)
                         .emoji('👉')
-                        .label("Then continue"),
+                        .label(locale.t(Msg::ThenContinueButton)),
                 ])]),
         ),
     )
@@ -43,18 +45,13 @@ pub(crate) async fn login_1(
     Ok(())
 }
 
-const LOGIN_FORM: &str = indoc::indoc! {"
-    Congratulations, your Imperial shortcode has been connected to your Discord Account by Nano!
-
-    The last step is a short form with some extra details
-"};
-
 #[tracing::instrument(skip_all)]
 pub(crate) async fn login_2(
     ctx: &serenity::Context,
     m: &serenity::ComponentInteraction,
     data: &Data,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
     match db::get_pending_by_id(&data.db, m.user.id.into()).await {
         Err(e) => {
             tracing::error!("{e}");
@@ -62,18 +59,19 @@ pub(crate) async fn login_2(
                 &ctx.http,
                 CreateInteractionResponse::Message(
                     CreateInteractionResponseMessage::new()
-                        .content("Sorry, something went wrong. Please try again")
+                        .content(locale.t(Msg::SomethingWrong))
                         .ephemeral(true),
                 ),
             )
             .await?;
         }
         Ok(None) => {
+            metrics::failed("login_incomplete");
             m.create_response(
                 &ctx.http,
                 CreateInteractionResponse::Message(
                     CreateInteractionResponseMessage::new()
-                        .content("Error, have you completed login verification via the link?")
+                        .content(locale.t(Msg::LoginIncomplete))
                         .ephemeral(true),
                 ),
             )
@@ -84,7 +82,7 @@ pub(crate) async fn login_2(
                 &ctx.http,
                 CreateInteractionResponse::UpdateMessage(
                     CreateInteractionResponseMessage::new()
-                        .content(LOGIN_FORM)
+                        .content(Template::LoginForm.render(data, &m.user, locale).await?)
                         .components(vec![CreateActionRow::Buttons(vec![
                             CreateButton::new("login_1")
                                 .style(serenity::ButtonStyle::Danger)
@@ -92,7 +90,7 @@ pub(crate) async fn login_2(
                             CreateButton::new("login_3")
                                 .style(serenity::ButtonStyle::Primary)
                                 .emoji('📑')
-                                .label("Form"),
+                                .label(locale.t(Msg::FormButton)),
                         ])]),
                 ),
             )
@@ -107,11 +105,12 @@ pub(crate) async fn login_3(
     ctx: &serenity::Context,
     m: &serenity::ComponentInteraction,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
     m.create_response(
         &ctx.http,
         CreateInteractionResponse::UpdateMessage(
             CreateInteractionResponseMessage::new()
-                .content("Are you a fresher?")
+                .content(locale.t(Msg::AreYouFresher))
                 .components(vec![CreateActionRow::Buttons(vec![
                     CreateButton::new("login_2")
                         .style(serenity::ButtonStyle::Danger)
@@ -119,15 +118,15 @@ pub(crate) async fn login_3(
                     CreateButton::new("login_4u")
                         .style(serenity::ButtonStyle::Success)
                         .emoji('✅')
-                        .label("Fresher"),
+                        .label(locale.t(Msg::FresherButton)),
                     CreateButton::new("login_4n")
                         .style(serenity::ButtonStyle::Primary)
                         .emoji('❌')
-                        .label("Non-fresher"),
+                        .label(locale.t(Msg::NonFresherButton)),
                     CreateButton::new("login_4p")
                         .style(serenity::ButtonStyle::Success)
                         .emoji('🎓')
-                        .label("Postgraduate fresher"),
+                        .label(locale.t(Msg::PgFresherButton)),
                 ])]),
         ),
     )
@@ -141,6 +140,7 @@ pub(crate) async fn login_4(
     m: &serenity::ComponentInteraction,
     fresher: Fresher,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
     let next = match fresher {
         Fresher::No => "login_5n",
         Fresher::YesPg => "login_5p",
@@ -151,7 +151,7 @@ pub(crate) async fn login_4(
         &ctx.http,
         CreateInteractionResponse::UpdateMessage(
             CreateInteractionResponseMessage::new()
-                .content("And a preferred name for Nano whois commands")
+                .content(locale.t(Msg::NicknamePrompt))
                 .components(vec![CreateActionRow::Buttons(vec![
                     CreateButton::new("login_3")
                         .style(serenity::ButtonStyle::Danger)
@@ -159,7 +159,7 @@ pub(crate) async fn login_4(
                     CreateButton::new(next)
                         .style(serenity::ButtonStyle::Primary)
                         .emoji('💬')
-                        .label("Name"),
+                        .label(locale.t(Msg::NameButton)),
                 ])]),
         ),
     )
@@ -167,11 +167,9 @@ pub(crate) async fn login_4(
     Ok(())
 }
 
+/// Created localised by `login_5`
 #[derive(Modal)]
-#[name = "Preferred Name"]
 struct Nickname {
-    #[name = "Preferred name for Nano whois commands"]
-    #[placeholder = "Firstname Lastname"]
     nickname: String,
 }
 
@@ -181,21 +179,46 @@ pub(crate) async fn login_5(
     m: &serenity::ComponentInteraction,
     fresher: Fresher,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
     m.create_response(
         &ctx.http,
-        Nickname::create(
-            None,
+        verify::modal(
             match fresher {
                 Fresher::No => "login_6n".to_string(),
                 Fresher::YesPg => "login_6p".to_string(),
                 Fresher::YesUg => "login_6u".to_string(),
             },
+            locale.t(Msg::NicknameTitle),
+            &[(
+                "nickname",
+                locale.t(Msg::NicknameLabel),
+                locale.t(Msg::NamePlaceholder),
+            )],
         ),
     )
     .await?;
     Ok(())
 }
 
+/// Verify user from the pending entry added by their login provider
+pub(crate) async fn verify_pending(
+    pool: &sqlx::SqlitePool,
+    user: serenity::UserId,
+    nickname: &str,
+    fresher: Fresher,
+) -> Result<Member, Error> {
+    db::insert_member_from_pending(
+        pool,
+        Actor::User(user),
+        user.into(),
+        nickname,
+        fresher,
+        Method::Login,
+        None,
+    )
+    .await
+}
+
 #[tracing::instrument(skip_all)]
 pub(crate) async fn login_6(
     ctx: &serenity::Context,
@@ -203,34 +226,29 @@ pub(crate) async fn login_6(
     data: &Data,
     fresher: Fresher,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
+    let guild = data.guild();
     match Nickname::parse(m.data.clone()) {
         Ok(Nickname { nickname }) => {
-            // Delete from manual if exists
-            let _ = db::delete_manual_by_id(&data.db, m.user.id.into()).await;
-
-            match db::insert_member_from_pending(&data.db, m.user.id.into(), &nickname, fresher)
-                .await
-            {
-                Ok(p) => {
+            match verify_pending(&data.db, m.user.id, &nickname, fresher).await {
+                Ok(member) => {
                     tracing::info!(
                         "{} ({}) added via login ({})",
                         m.user.name,
                         m.user.id,
                         fresher
                     );
+                    metrics::verified(Method::Login, fresher);
+                    data.webhooks.wake();
                     let mut mm = m.member.clone().unwrap();
-                    verify::apply_role(ctx, &mut mm, data.member).await?;
-                    match fresher {
-                        Fresher::No => {}
-                        Fresher::YesPg => verify::apply_role(ctx, &mut mm, data.fresher_pg).await?,
-                        Fresher::YesUg => verify::apply_role(ctx, &mut mm, data.fresher_ug).await?,
+                    verify::apply_role(ctx, &mut mm, guild.member).await?;
+                    if let Some(role) = guild.fresher_role(fresher) {
+                        verify::apply_role(ctx, &mut mm, role).await?;
                     }
                     let msg = if matches!(fresher, Fresher::No) {
-                        "Congratulations, you have completed verification and now \
-                        have access to the ICAS Discord"
+                        locale.t(Msg::Verified)
                     } else {
-                        "Congratulations, you have completed verification and now \
-                        have access to the ICAS Discord and freshers thread"
+                        locale.t(Msg::VerifiedFresher)
                     };
                     m.create_response(
                         &ctx.http,
@@ -241,26 +259,28 @@ pub(crate) async fn login_6(
                         ),
                     )
                     .await?;
-                    data.au_ch_id
+                    guild
+                        .au_ch_id
                         .send_message(
                             &ctx.http,
-                            CreateMessage::new().embed(
+                            CreateMessage::new().embed(verify::verification_fields(
                                 CreateEmbed::new()
                                     .thumbnail(m.user.face())
                                     .title("Member verified via login")
                                     .description(m.user.to_string())
                                     .field("Fresher", fresher.to_string(), true)
                                     .field("Nickname", nickname, true)
-                                    .field("Name", p.realname, true)
+                                    .field("Name", &member.realname, true)
                                     .timestamp(serenity::Timestamp::now()),
-                            ),
+                                &member,
+                            )),
                         )
                         .await?;
-                    let _ = mm.remove_role(&ctx.http, data.non_member).await;
-                    if mm.roles.contains(&data.old_member) {
-                        verify::remove_role(ctx, &mut mm, data.old_member).await?;
+                    let _ = mm.remove_role(&ctx.http, guild.non_member).await;
+                    if mm.roles.contains(&guild.old_member) {
+                        verify::remove_role(ctx, &mut mm, guild.old_member).await?;
                     } else {
-                        verify::welcome_user(&ctx.http, &data.gn_ch_id, &m.user, fresher).await?;
+                        verify::welcome_user(&ctx.http, data, &m.user, fresher, locale).await?;
                     }
                 }
                 Err(e) => {
@@ -269,7 +289,7 @@ pub(crate) async fn login_6(
                         &ctx.http,
                         CreateInteractionResponse::Message(
                             CreateInteractionResponseMessage::new()
-                                .content("Sorry, something went wrong. Please try again")
+                                .content(locale.t(Msg::SomethingWrong))
                                 .ephemeral(true),
                         ),
                     )
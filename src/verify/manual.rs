@@ -1,34 +1,29 @@
-use crate::{db, verify, Data, Error, Fresher, Gaijin, ManualMember};
+use crate::{
+    db,
+    locale::{Locale, Msg},
+    metrics,
+    templates::Template,
+    verify, Actor, Data, Error, Flow, Fresher, Gaijin, ManualMember, Member, Method,
+};
 use poise::serenity_prelude::{
     self as serenity, CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse,
     CreateInteractionResponseMessage, CreateMessage,
 };
 use poise::Modal;
 
-const MANUAL_INTRO: &str = indoc::indoc! {"
-    Submit details to be manually checked by a committee member:
-    - Your Imperial Shortcode
-    - Your First and Last Names as on your Imperial record
-    - Preferred First and Last Names for the Nano whois command
-    - URL to proof of being an Imperial student, e.g. photo of College ID Card \
-        or screenshot of College Acceptance Letter, if you need to upload this, \
-        you can send it in a DM and then copy the image URL
-
-    We try to respond quickly but this may take a day or two during busy term times :)
-
-    First, are you a fresher? (And if yes, a postgraduate fresher?)
-"};
-
 #[tracing::instrument(skip_all)]
 pub(crate) async fn manual_1(
     ctx: &serenity::Context,
     m: &serenity::ComponentInteraction,
+    data: &Data,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
+    let content = Template::ManualIntro.render(data, &m.user, locale).await?;
     m.create_response(
         &ctx.http,
         CreateInteractionResponse::UpdateMessage(
             CreateInteractionResponseMessage::new()
-                .content(MANUAL_INTRO)
+                .content(content)
                 .components(vec![CreateActionRow::Buttons(vec![
                     CreateButton::new("restart")
                         .style(serenity::ButtonStyle::Danger)
@@ -36,15 +31,15 @@ pub(crate) async fn manual_1(
                     CreateButton::new("manual_2u")
                         .style(serenity::ButtonStyle::Success)
                         .emoji('✅')
-                        .label("Fresher"),
+                        .label(locale.t(Msg::FresherButton)),
                     CreateButton::new("manual_2n")
                         .style(serenity::ButtonStyle::Primary)
                         .emoji('❌')
-                        .label("Non-fresher"),
+                        .label(locale.t(Msg::NonFresherButton)),
                     CreateButton::new("manual_2p")
                         .style(serenity::ButtonStyle::Success)
                         .emoji('🎓')
-                        .label("Postgraduate fresher"),
+                        .label(locale.t(Msg::PgFresherButton)),
                 ])]),
         ),
     )
@@ -52,21 +47,12 @@ pub(crate) async fn manual_1(
     Ok(())
 }
 
+/// Created localised by `manual_2`
 #[derive(Modal)]
-#[name = "Manual Verification"]
 struct Manual {
-    #[name = "Imperial Shortcode"]
-    #[placeholder = "ab1234"]
     shortcode: String,
-    #[name = "Name as on Imperial record"]
-    #[placeholder = "Firstname Lastname"]
     realname: String,
-    #[name = "URL to proof image"]
-    #[placeholder = "E.g. photo of College ID Card \
-    or screenshot of College Acceptance Letter"]
     url: String,
-    #[name = "Preferred name for Nano whois commands"]
-    #[placeholder = "Firstname Lastname"]
     nickname: String,
 }
 
@@ -77,18 +63,37 @@ pub(crate) async fn manual_2(
     data: &Data,
     fresher: Fresher,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
     // Delete from manual if exists
-    let _ = db::delete_manual_by_id(&data.db, m.user.id.into()).await;
+    let _ = db::delete_manual_by_id(&data.db, Actor::User(m.user.id), m.user.id.into()).await;
 
     m.create_response(
         &ctx.http,
-        Manual::create(
-            None,
+        verify::modal(
             match fresher {
                 Fresher::No => "manual_3n".to_string(),
                 Fresher::YesPg => "manual_3p".to_string(),
                 Fresher::YesUg => "manual_3u".to_string(),
             },
+            locale.t(Msg::ManualTitle),
+            &[
+                ("shortcode", locale.t(Msg::ShortcodeLabel), "ab1234"),
+                (
+                    "realname",
+                    locale.t(Msg::RealnameLabel),
+                    locale.t(Msg::NamePlaceholder),
+                ),
+                (
+                    "url",
+                    locale.t(Msg::UrlLabel),
+                    locale.t(Msg::UrlPlaceholder),
+                ),
+                (
+                    "nickname",
+                    locale.t(Msg::NicknameLabel),
+                    locale.t(Msg::NamePlaceholder),
+                ),
+            ],
         ),
     )
     .await?;
@@ -103,6 +108,7 @@ pub(crate) async fn manual_3(
     data: &Data,
     fresher: Fresher,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
     match Manual::parse(m.data.clone()) {
         Ok(Manual {
             shortcode,
@@ -110,12 +116,17 @@ pub(crate) async fn manual_3(
             url,
             nickname,
         }) => {
+            if verify::locked_out(ctx, m, data, Flow::Manual).await? {
+                return Ok(());
+            }
             if ::url::Url::parse(&url).is_err() {
+                metrics::failed("invalid_url");
+                verify::failed_attempt(&ctx.http, &m.user, data, Flow::Manual).await?;
                 m.create_response(
                     &ctx.http,
                     CreateInteractionResponse::Message(
                         CreateInteractionResponseMessage::new()
-                            .content("The url provided is invalid, please try again")
+                            .content(locale.t(Msg::InvalidUrl))
                             .ephemeral(true),
                     ),
                 )
@@ -123,10 +134,8 @@ pub(crate) async fn manual_3(
                 return Ok(());
             }
 
-            // Delete from pending if exists
-            let _ = db::delete_pending_by_id(&data.db, m.user.id.into()).await?;
-
-            let prompt_sent = data
+            let prompt = data
+                .guild()
                 .au_ch_id
                 .send_message(
                     &ctx.http,
@@ -159,11 +168,13 @@ pub(crate) async fn manual_3(
                                 .label("Gaijin"),
                         ])]),
                 )
-                .await
-                .is_ok();
+                .await;
+            let prompt_sent = prompt.is_ok();
 
-            let inserted = db::insert_manual(
+            // Replaces pending entry if exists
+            let inserted = db::insert_manual_replacing_pending(
                 &data.db,
+                Actor::User(m.user.id),
                 ManualMember {
                     discord_id: m.user.id.into(),
                     shortcode,
@@ -175,14 +186,26 @@ pub(crate) async fn manual_3(
             .await
             .is_ok();
 
+            if inserted {
+                let (channel_id, message_id) = prompt
+                    .map(|p| (Some(p.channel_id.into()), Some(p.id.into())))
+                    .unwrap_or_default();
+                let id = m.user.id.into();
+                if let Err(e) =
+                    db::insert_review_request(&data.db, id, &url, channel_id, message_id).await
+                {
+                    tracing::error!("Failed to store review request for {}: {e}", m.user.id);
+                }
+            }
+
             let msg = if prompt_sent {
                 if inserted {
-                    "Thanks, your verification request has been sent, we'll try to get back to you quickly!"
+                    locale.t(Msg::RequestSent)
                 } else {
-                    "Thanks, your verification request has been sent, but there was an issue, please ask a Committee member to take a look!"
+                    locale.t(Msg::RequestSentIssue)
                 }
             } else {
-                "Sending your verification request failed, please try again, or contact an Admin if it happens again."
+                locale.t(Msg::RequestFailed)
             };
 
             m.create_response(
@@ -202,7 +225,7 @@ pub(crate) async fn manual_3(
                 &ctx.http,
                 CreateInteractionResponse::Message(
                     CreateInteractionResponseMessage::new()
-                        .content("Sorry, something went wrong. Please try again, or contact an Admin if it happens again.")
+                        .content(locale.t(Msg::SomethingWrongContact))
                         .ephemeral(true),
                 ),
             )
@@ -224,7 +247,109 @@ struct ManualGaijin {
     university: String,
 }
 
-#[allow(clippy::too_many_lines, reason = "Lots of logic")]
+/// Add user to members table from manual table, and apply their roles
+#[tracing::instrument(skip_all)]
+pub(crate) async fn accept_manual(
+    http: &serenity::Http,
+    data: &Data,
+    user: &serenity::User,
+    actor: Actor<'_>,
+    verified_by: Option<i64>,
+) -> Result<Member, Error> {
+    let guild = data.guild();
+    let mut member = guild.server.member(http, user).await?;
+    let mm = db::insert_member_from_manual(&data.db, actor, user.id.into(), verified_by).await?;
+    tracing::info!(
+        "{} ({}) added via manual ({})",
+        user.name,
+        user.id,
+        mm.fresher
+    );
+    metrics::verified(Method::Manual, mm.fresher);
+    data.webhooks.wake();
+    db::clear_attempts(&data.db, user.id.into(), Flow::Manual).await?;
+    verify::apply_role(http, &mut member, guild.member).await?;
+    if let Some(role) = guild.fresher_role(mm.fresher) {
+        verify::apply_role(http, &mut member, role).await?;
+    }
+    let _ = member.remove_role(http, guild.non_member).await;
+    if member.roles.contains(&guild.old_member) {
+        verify::remove_role(http, &mut member, guild.old_member).await?;
+    } else {
+        verify::welcome_user(http, data, user, mm.fresher, Locale::En).await?;
+    }
+    Ok(mm)
+}
+
+/// Remove user from manual table, counting the denial as a failed attempt
+#[tracing::instrument(skip_all)]
+pub(crate) async fn deny_manual(
+    http: &serenity::Http,
+    data: &Data,
+    user: &serenity::User,
+    actor: Actor<'_>,
+) -> Result<(), Error> {
+    db::deny_manual_by_id(&data.db, actor, user.id.into()).await?;
+    tracing::info!("{} ({}) denied via manual", user.name, user.id);
+    data.webhooks.wake();
+    verify::failed_attempt(http, user, data, Flow::Manual).await
+}
+
+/// Move user from manual table to gaijin table, and apply the gaijin role
+#[tracing::instrument(skip_all)]
+pub(crate) async fn manual_to_gaijin(
+    http: &serenity::Http,
+    data: &Data,
+    user: &serenity::User,
+    actor: Actor<'_>,
+    gaijin: Gaijin,
+) -> Result<(), Error> {
+    let guild = data.guild();
+    db::insert_gaijin_from_manual(&data.db, actor, gaijin).await?;
+
+    let member = guild.server.member(http, user).await?;
+    let _ = member.remove_role(http, guild.non_member).await;
+    let _ = member.add_role(http, guild.gaijin).await;
+
+    tracing::info!("{} ({}) added as gaijin via manual", user.name, user.id);
+    data.webhooks.wake();
+    Ok(())
+}
+
+/// Review request embed once the user has been verified
+pub(crate) fn accepted_embed(user: &serenity::User, mm: &Member, by: &str) -> CreateEmbed {
+    let embed = CreateEmbed::new()
+        .thumbnail(user.face())
+        .title("Member verified via manual")
+        .description(user.to_string())
+        .field("Fresher", mm.fresher.to_string(), true)
+        .field("Nickname", &mm.nickname, true)
+        .field("Name", &mm.realname, true)
+        .field("Verified by", by, true)
+        .timestamp(serenity::Timestamp::now());
+    verify::verification_fields(embed, mm)
+}
+
+/// Review request embed once the user has been denied
+pub(crate) fn denied_embed(user: &serenity::User) -> CreateEmbed {
+    CreateEmbed::new()
+        .title("Member denied via manual")
+        .description(user.to_string())
+        .thumbnail(user.face())
+        .timestamp(serenity::Timestamp::now())
+}
+
+/// Review request embed once the user has been added as a gaijin
+pub(crate) fn gaijin_embed(user: &serenity::User, name: &str, university: &str) -> CreateEmbed {
+    CreateEmbed::new()
+        .thumbnail(user.face())
+        .title("Gaijin verified via manual")
+        .description(user.to_string())
+        .field("Name", name, true)
+        .field("University", university, true)
+        .timestamp(serenity::Timestamp::now())
+}
+
 #[tracing::instrument(skip_all)]
 pub(crate) async fn manual_4(
     ctx: &serenity::Context,
@@ -233,48 +358,21 @@ pub(crate) async fn manual_4(
     id: &str,
 ) -> Result<(), Error> {
     let user = id_to_user(ctx, id).await;
-
-    let mut member = data.server.member(&ctx.http, &user).await?;
+    let actor = Actor::User(m.user.id);
+    let by = Some(m.user.id.into());
 
     match id.chars().nth(7) {
-        Some('y') => match db::insert_member_from_manual(&data.db, user.id.into()).await {
+        Some('y') => match accept_manual(&ctx.http, data, &user, actor, by).await {
             Ok(mm) => {
-                tracing::info!(
-                    "{} ({}) added via manual ({})",
-                    user.name,
-                    user.id,
-                    mm.fresher
-                );
-                verify::apply_role(ctx, &mut member, data.member).await?;
-                match mm.fresher {
-                    Fresher::No => {}
-                    Fresher::YesPg => verify::apply_role(ctx, &mut member, data.fresher_pg).await?,
-                    Fresher::YesUg => verify::apply_role(ctx, &mut member, data.fresher_ug).await?,
-                }
                 m.create_response(
                     &ctx.http,
                     CreateInteractionResponse::UpdateMessage(
                         CreateInteractionResponseMessage::new()
                             .components(vec![])
-                            .embed(
-                                CreateEmbed::new()
-                                    .thumbnail(user.face())
-                                    .title("Member verified via manual")
-                                    .description(user.to_string())
-                                    .field("Fresher", mm.fresher.to_string(), true)
-                                    .field("Nickname", mm.nickname, true)
-                                    .field("Name", mm.realname, true)
-                                    .timestamp(serenity::Timestamp::now()),
-                            ),
+                            .embed(accepted_embed(&user, &mm, &m.user.to_string())),
                     ),
                 )
                 .await?;
-                let _ = member.remove_role(&ctx.http, data.non_member).await;
-                if member.roles.contains(&data.old_member) {
-                    verify::remove_role(ctx, &mut member, data.old_member).await?;
-                } else {
-                    verify::welcome_user(&ctx.http, &data.gn_ch_id, &user, mm.fresher).await?;
-                }
             }
             Err(e) => {
                 tracing::error!("{e}");
@@ -289,20 +387,13 @@ pub(crate) async fn manual_4(
             }
         },
         Some('n') => {
-            db::delete_manual_by_id(&data.db, user.id.into()).await?;
-            tracing::info!("{} ({}) denied via manual", user.name, user.id);
+            deny_manual(&ctx.http, data, &user, actor).await?;
             m.create_response(
                 &ctx.http,
                 CreateInteractionResponse::UpdateMessage(
                     CreateInteractionResponseMessage::new()
                         .components(vec![])
-                        .embed(
-                            CreateEmbed::new()
-                                .title("Member denied via manual")
-                                .description(user.to_string())
-                                .thumbnail(user.face())
-                                .timestamp(serenity::Timestamp::now()),
-                        ),
+                        .embed(denied_embed(&user)),
                 ),
             )
             .await?;
@@ -359,33 +450,31 @@ pub(crate) async fn manual_5(
     match ManualGaijin::parse(m.data.clone()) {
         Ok(ManualGaijin { name, university }) => {
             let user = id_to_user(ctx, id).await;
-            let _ = db::delete_manual_by_id(&data.db, user.id.into()).await?;
             let gaijin = Gaijin {
                 discord_id: user.id.into(),
                 name: name.clone(),
                 university: university.clone(),
             };
-            let _ = db::insert_gaijin(&data.db, gaijin).await;
-
-            let member = data.server.member(&ctx.http, &user).await?;
-            let _ = member.remove_role(&ctx.http, data.non_member).await;
-            let _ = member.add_role(&ctx.http, data.gaijin).await;
+            let actor = Actor::User(m.user.id);
+            if let Err(e) = manual_to_gaijin(&ctx.http, data, &user, actor, gaijin).await {
+                tracing::error!("{e}");
+                m.create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content(format!("Failed to add user {user} to gaijin database")),
+                    ),
+                )
+                .await?;
+                return Ok(());
+            }
 
-            tracing::info!("{} ({}) added as gaijin via manual", user.name, user.id);
             m.create_response(
                 &ctx.http,
                 CreateInteractionResponse::UpdateMessage(
                     CreateInteractionResponseMessage::new()
                         .components(vec![])
-                        .embed(
-                            CreateEmbed::new()
-                                .thumbnail(user.face())
-                                .title("Gaijin verified via manual")
-                                .description(user.to_string())
-                                .field("Name", name, true)
-                                .field("University", university, true)
-                                .timestamp(serenity::Timestamp::now()),
-                        ),
+                        .embed(gaijin_embed(&user, &name, &university)),
                 ),
             )
             .await?;
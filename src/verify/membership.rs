@@ -1,32 +1,31 @@
-use crate::{db, ea, verify, Data, Error, Fresher, Member};
+use crate::{
+    db,
+    locale::{Locale, Msg},
+    metrics,
+    templates::Template,
+    verify, Actor, Data, Error, Flow, Fresher, Member, Method,
+};
 use poise::serenity_prelude::{
     self as serenity, CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponse,
     CreateInteractionResponseMessage, CreateMessage,
 };
 use poise::Modal;
 
-const MEMBERSHIP_INTRO: &str = indoc::indoc! {"
-    To use automatic verification via Membership:
-    - Enter your Union order number (from this academic year)
-    - Enter your Imperial shortcode
-      - For Life members, your shortcode will be from when you were a student
-      - For Associate members, this is your CID, in the format `AM-12345` or similar
-    - Enter your preferred name for Nano whois commands
-    - Your shortcode will then be connected to your Discord Account by Nano
-
-    First, are you a fresher?
-"};
-
 #[tracing::instrument(skip_all)]
 pub(crate) async fn membership_1(
     ctx: &serenity::Context,
     m: &serenity::ComponentInteraction,
+    data: &Data,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
+    let content = Template::MembershipIntro
+        .render(data, &m.user, locale)
+        .await?;
     m.create_response(
         &ctx.http,
         CreateInteractionResponse::UpdateMessage(
             CreateInteractionResponseMessage::new()
-                .content(MEMBERSHIP_INTRO)
+                .content(content)
                 .components(vec![CreateActionRow::Buttons(vec![
                     CreateButton::new("restart")
                         .style(serenity::ButtonStyle::Danger)
@@ -34,15 +33,15 @@ pub(crate) async fn membership_1(
                     CreateButton::new("membership_2u")
                         .style(serenity::ButtonStyle::Success)
                         .emoji('✅')
-                        .label("Fresher"),
+                        .label(locale.t(Msg::FresherButton)),
                     CreateButton::new("membership_2n")
                         .style(serenity::ButtonStyle::Primary)
                         .emoji('❌')
-                        .label("Non-fresher"),
+                        .label(locale.t(Msg::NonFresherButton)),
                     CreateButton::new("membership_2p")
                         .style(serenity::ButtonStyle::Success)
                         .emoji('🎓')
-                        .label("Postgraduate fresher"),
+                        .label(locale.t(Msg::PgFresherButton)),
                 ])]),
         ),
     )
@@ -50,17 +49,11 @@ pub(crate) async fn membership_1(
     Ok(())
 }
 
+/// Created localised by `membership_2`
 #[derive(Modal)]
-#[name = "ICAS Membership Verification"]
 struct Membership {
-    #[name = "ICAS Membership Union Order Number"]
-    #[placeholder = "1234567"]
     order: String,
-    #[name = "Imperial Shortcode"]
-    #[placeholder = "ab1234"]
     shortcode: String,
-    #[name = "Preferred name for Nano whois commands"]
-    #[placeholder = "Firstname Lastname"]
     nickname: String,
 }
 
@@ -71,21 +64,29 @@ pub(crate) async fn membership_2(
     data: &Data,
     fresher: Fresher,
 ) -> Result<(), Error> {
-    // Delete from pending if exists
-    let _ = db::delete_pending_by_id(&data.db, m.user.id.into()).await;
-
-    // Delete from manual if exists
-    let _ = db::delete_manual_by_id(&data.db, m.user.id.into()).await;
+    let locale = Locale::from_discord(&m.locale);
+    // Delete from pending and manual if exists
+    let _ = db::delete_pending_and_manual_by_id(&data.db, Actor::User(m.user.id), m.user.id.into())
+        .await;
 
     m.create_response(
         &ctx.http,
-        Membership::create(
-            None,
+        verify::modal(
             match fresher {
                 Fresher::No => "membership_3n".to_string(),
                 Fresher::YesPg => "membership_3p".to_string(),
                 Fresher::YesUg => "membership_3u".to_string(),
             },
+            locale.t(Msg::MembershipTitle),
+            &[
+                ("order", locale.t(Msg::OrderLabel), "1234567"),
+                ("shortcode", locale.t(Msg::ShortcodeLabel), "ab1234"),
+                (
+                    "nickname",
+                    locale.t(Msg::NicknameLabel),
+                    locale.t(Msg::NamePlaceholder),
+                ),
+            ],
         ),
     )
     .await?;
@@ -100,18 +101,23 @@ pub(crate) async fn membership_3(
     data: &Data,
     fresher: Fresher,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
+    let guild = data.guild();
     match Membership::parse(m.data.clone()) {
         Ok(Membership {
             order,
             shortcode,
             nickname,
         }) => {
-            let members = match ea::get_members_list(&data.ea_key, &data.ea_url).await {
+            if verify::locked_out(ctx, m, data, Flow::Membership).await? {
+                return Ok(());
+            }
+            let member = match data.ea.find(&order, &shortcode).await {
                 Ok(v) => v,
                 Err(e) => {
                     tracing::error!("{e}");
-                    let msg = "Sorry, getting membership data failed. \
-                        Please try again or contact an Admin";
+                    metrics::failed("ea_unavailable");
+                    let msg = locale.t(Msg::EaUnavailable);
                     m.create_response(
                         &ctx.http,
                         CreateInteractionResponse::Message(
@@ -124,12 +130,10 @@ pub(crate) async fn membership_3(
                     return Ok(());
                 }
             };
-            let Some(member) = members.iter().find(|member| {
-                ((member.login.is_empty() && member.cid == shortcode) || member.login == shortcode)
-                    && member.order_no.to_string() == order
-            }) else {
-                let msg = "Sorry, your order was not found, please check the \
-                    order number and that it is for your current year's membership";
+            let Some(member) = member else {
+                metrics::failed("order_not_found");
+                verify::failed_attempt(&ctx.http, &m.user, data, Flow::Membership).await?;
+                let msg = locale.t(Msg::OrderNotFound);
                 m.create_response(
                     &ctx.http,
                     CreateInteractionResponse::Message(
@@ -142,51 +146,94 @@ pub(crate) async fn membership_3(
                 return Ok(());
             };
             let realname = format!("{} {}", member.first_name, member.surname);
-            if db::insert_member(
+            let order_no = i64::try_from(member.order_no)?;
+            let member = Member {
+                discord_id: m.user.id.into(),
+                shortcode: shortcode.clone(),
+                nickname: nickname.clone(),
+                realname: realname.clone(),
+                fresher,
+                verified_at: Some(serenity::Timestamp::now().unix_timestamp()),
+                method: Method::Membership,
+                verified_by: None,
+            };
+            let claim = db::insert_member_claiming_order(
                 &data.db,
-                Member {
-                    discord_id: m.user.id.into(),
-                    shortcode,
-                    nickname: nickname.clone(),
-                    realname: realname.clone(),
-                    fresher,
-                },
+                Actor::User(m.user.id),
+                member.clone(),
+                order_no,
             )
-            .await
-            .is_ok()
-            {
+            .await;
+            if let Ok(Some(claimant)) = claim {
+                metrics::failed("order_claimed");
+                verify::failed_attempt(&ctx.http, &m.user, data, Flow::Membership).await?;
+                tracing::warn!(
+                    "{} ({}) tried to claim order {order_no}, already claimed by {claimant}",
+                    m.user.name,
+                    m.user.id
+                );
+                let msg = locale.t(Msg::OrderClaimed);
+                m.create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content(msg)
+                            .ephemeral(true),
+                    ),
+                )
+                .await?;
+                guild
+                    .au_ch_id
+                    .send_message(
+                        &ctx.http,
+                        CreateMessage::new().embed(
+                            CreateEmbed::new()
+                                .thumbnail(m.user.face())
+                                .title("Order number already claimed")
+                                .description(m.user.to_string())
+                                .field("Order", order_no.to_string(), true)
+                                .field("Shortcode", shortcode, true)
+                                .field("Claimed by", format!("<@{claimant}>"), true)
+                                .field("Attempted by", m.user.to_string(), true)
+                                .timestamp(serenity::Timestamp::now()),
+                        ),
+                    )
+                    .await?;
+                return Ok(());
+            }
+            if claim.is_ok() {
+                db::clear_attempts(&data.db, m.user.id.into(), Flow::Membership).await?;
                 tracing::info!(
                     "{} ({}) added via membership ({})",
                     m.user.name,
                     m.user.id,
                     fresher
                 );
+                metrics::verified(Method::Membership, fresher);
+                data.webhooks.wake();
                 let mut mm = m.member.clone().unwrap();
-                verify::apply_role(ctx, &mut mm, data.member).await?;
-                match fresher {
-                    Fresher::No => {}
-                    Fresher::YesPg => verify::apply_role(ctx, &mut mm, data.fresher_pg).await?,
-                    Fresher::YesUg => verify::apply_role(ctx, &mut mm, data.fresher_ug).await?,
+                verify::apply_role(ctx, &mut mm, guild.member).await?;
+                if let Some(role) = guild.fresher_role(fresher) {
+                    verify::apply_role(ctx, &mut mm, role).await?;
                 }
                 m.create_response(
                     &ctx.http,
                     CreateInteractionResponse::UpdateMessage(
                         CreateInteractionResponseMessage::new()
                             .content(if matches!(fresher, Fresher::No) {
-                                "Congratulations, you have completed verification and now \
-                                have access to the ICAS Discord"
+                                locale.t(Msg::Verified)
                             } else {
-                                "Congratulations, you have completed verification and now \
-                                have access to the ICAS Discord and freshers thread"
+                                locale.t(Msg::VerifiedFresher)
                             })
                             .components(vec![]),
                     ),
                 )
                 .await?;
-                data.au_ch_id
+                guild
+                    .au_ch_id
                     .send_message(
                         &ctx.http,
-                        CreateMessage::new().embed(
+                        CreateMessage::new().embed(verify::verification_fields(
                             CreateEmbed::new()
                                 .thumbnail(m.user.face())
                                 .title("Member verified via membership")
@@ -195,14 +242,15 @@ pub(crate) async fn membership_3(
                                 .field("Nickname", nickname, true)
                                 .field("Name", realname, true)
                                 .timestamp(serenity::Timestamp::now()),
-                        ),
+                            &member,
+                        )),
                     )
                     .await?;
-                let _ = mm.remove_role(&ctx.http, data.non_member).await;
-                if mm.roles.contains(&data.old_member) {
-                    verify::remove_role(ctx, &mut mm, data.old_member).await?;
+                let _ = mm.remove_role(&ctx.http, guild.non_member).await;
+                if mm.roles.contains(&guild.old_member) {
+                    verify::remove_role(ctx, &mut mm, guild.old_member).await?;
                 } else {
-                    verify::welcome_user(&ctx.http, &data.gn_ch_id, &m.user, fresher).await?;
+                    verify::welcome_user(&ctx.http, data, &m.user, fresher, locale).await?;
                 }
                 return Ok(());
             }
@@ -213,7 +261,7 @@ pub(crate) async fn membership_3(
         &ctx.http,
         CreateInteractionResponse::Message(
             CreateInteractionResponseMessage::new()
-                .content("Sorry, something went wrong. Please try again or contact an Admin")
+                .content(locale.t(Msg::SomethingWrongContact))
                 .ephemeral(true),
         ),
     )
@@ -1,7 +1,13 @@
-use crate::{db, Data, Error, Fresher};
+use crate::{
+    db,
+    locale::{Locale, Msg},
+    metrics, templates,
+    templates::Template,
+    Data, Error, Flow, Fresher, Member,
+};
 use poise::serenity_prelude::{
-    self as serenity, CacheHttp, CreateActionRow, CreateButton, CreateInteractionResponse,
-    CreateInteractionResponseMessage, CreateMessage,
+    self as serenity, CacheHttp, CreateActionRow, CreateButton, CreateEmbed, CreateInputText,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, CreateModal,
 };
 
 pub(crate) mod login;
@@ -13,24 +19,19 @@ pub(crate) use membership::*;
 pub(crate) mod manual;
 pub(crate) use manual::*;
 
-const INFO_MSG: &str = indoc::indoc! {"
-    Nano is a Discord bot written with serenity-rs/poise and tokio-rs/axum.
-
-    It allows members and Imperial students to automatically verify themselves and gain access to the ICAS Discord server.
-
-    If you have any questions, feel free to ping or message <@99217900254035968>
-"};
-
 #[tracing::instrument(skip_all)]
 pub(crate) async fn info(
     ctx: &serenity::Context,
     m: &serenity::ComponentInteraction,
+    data: &Data,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
+    let content = Template::Info.render(data, &m.user, locale).await?;
     m.create_response(
         &ctx.http,
         CreateInteractionResponse::Message(
             CreateInteractionResponseMessage::new()
-                .content(INFO_MSG)
+                .content(content)
                 .ephemeral(true),
         ),
     )
@@ -42,28 +43,136 @@ pub(crate) async fn info(
 pub(crate) async fn unknown(
     ctx: &serenity::Context,
     m: &serenity::ComponentInteraction,
+    data: &Data,
+) -> Result<(), Error> {
+    let content = Locale::from_discord(&m.locale)
+        .t(Msg::Unknown)
+        .replace("{contact}", &format!("<@&{}>", data.committee));
+    m.create_response(
+        &ctx.http,
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .ephemeral(true),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Add how and when a member was verified to an AU channel embed
+pub(crate) fn verification_fields(embed: CreateEmbed, member: &Member) -> CreateEmbed {
+    let verified_at = member
+        .verified_at
+        .map_or("Unknown".to_string(), |t| format!("<t:{t}:f>"));
+    embed
+        .field("Method", member.method.to_string(), true)
+        .field("Verified at", verified_at, true)
+}
+
+const UNAUTHORISED_MSG: &str = "Sorry, only committee members and reviewers can do this";
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn unauthorised(
+    ctx: &serenity::Context,
+    m: &serenity::ComponentInteraction,
+) -> Result<(), Error> {
+    m.create_response(
+        &ctx.http,
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(UNAUTHORISED_MSG)
+                .ephemeral(true),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(skip_all)]
+pub(crate) async fn unauthorised_modal(
+    ctx: &serenity::Context,
+    m: &serenity::ModalInteraction,
 ) -> Result<(), Error> {
+    m.create_response(
+        &ctx.http,
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content(UNAUTHORISED_MSG)
+                .ephemeral(true),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Reply to modal submit and return true if user is locked out of a verification flow
+#[tracing::instrument(skip_all)]
+pub(crate) async fn locked_out(
+    ctx: &serenity::Context,
+    m: &serenity::ModalInteraction,
+    data: &Data,
+    flow: Flow,
+) -> Result<bool, Error> {
+    let Some(until) = db::get_lockout(&data.db, m.user.id.into(), flow).await? else {
+        return Ok(false);
+    };
+    tracing::info!("{} ({}) locked out of {flow}", m.user.name, m.user.id);
+    metrics::failed("locked_out");
     m.create_response(
         &ctx.http,
         CreateInteractionResponse::Message(
             CreateInteractionResponseMessage::new()
                 .content(
-                    "Sorry, something went wrong. Please try again \
-                    or message <@99217900254035968> for help",
+                    Locale::from_discord(&m.locale)
+                        .t(Msg::LockedOut)
+                        .replace("{until}", &format!("<t:{until}:R>")),
                 )
                 .ephemeral(true),
         ),
     )
     .await?;
-    Ok(())
+    Ok(true)
 }
 
-const START_MSG: &str = indoc::indoc! {"
-    There are 3 available methods for verification.
-    - 🚀 Automatic verification via Imperial Login (Quickest)
-    - ✈️ Automatic verification via ICAS Membership (Easiest)
-    - 🚗 Manual verification, eg. using College ID Card or Acceptance Letter
-"};
+/// Record failed verification attempt, alerting the added users channel on lockout
+#[tracing::instrument(skip_all)]
+pub(crate) async fn failed_attempt(
+    http: &serenity::Http,
+    user: &serenity::User,
+    data: &Data,
+    flow: Flow,
+) -> Result<(), Error> {
+    let locked = db::record_failed_attempt(
+        &data.db,
+        user.id.into(),
+        flow,
+        data.max_attempts,
+        data.attempt_window,
+        data.attempt_cooldown,
+    )
+    .await?;
+    if let Some(until) = locked {
+        tracing::warn!("{} ({}) locked out of {flow}", user.name, user.id);
+        data.guild()
+            .au_ch_id
+            .send_message(
+                http,
+                CreateMessage::new().embed(
+                    CreateEmbed::new()
+                        .thumbnail(user.face())
+                        .title("Repeated verification attempts")
+                        .description(user.to_string())
+                        .field("Method", flow.to_string(), true)
+                        .field("Attempts", data.max_attempts.to_string(), true)
+                        .field("Locked until", format!("<t:{until}:f>"), true)
+                        .timestamp(serenity::Timestamp::now()),
+                ),
+            )
+            .await?;
+    }
+    Ok(())
+}
 
 #[tracing::instrument(skip_all)]
 pub(crate) async fn start(
@@ -72,42 +181,42 @@ pub(crate) async fn start(
     data: &Data,
     init: bool,
 ) -> Result<(), Error> {
+    let locale = Locale::from_discord(&m.locale);
     // Check if user is already verified
     if let Some(member) = db::get_member_by_id(&data.db, m.user.id.into()).await? {
+        let guild = data.guild();
         let mut mm = m.member.clone().unwrap();
-        remove_role(ctx, &mut mm, data.non_member).await?;
-        apply_role(ctx, &mut mm, data.member).await?;
-        match member.fresher {
-            Fresher::No => {}
-            Fresher::YesPg => apply_role(ctx, &mut mm, data.fresher_pg).await?,
-            Fresher::YesUg => apply_role(ctx, &mut mm, data.fresher_ug).await?,
+        remove_role(ctx, &mut mm, guild.non_member).await?;
+        apply_role(ctx, &mut mm, guild.member).await?;
+        if let Some(role) = guild.fresher_role(member.fresher) {
+            apply_role(ctx, &mut mm, role).await?;
         }
         m.create_response(
             &ctx.http,
             CreateInteractionResponse::Message(
                 CreateInteractionResponseMessage::new()
-                    .content("Welcome, you're already verified, re-applied your roles!")
+                    .content(locale.t(Msg::AlreadyVerified))
                     .ephemeral(true),
             ),
         )
         .await?;
     } else {
         let irm = CreateInteractionResponseMessage::new()
-            .content(START_MSG)
+            .content(Template::Start.render(data, &m.user, locale).await?)
             .ephemeral(true)
             .components(vec![CreateActionRow::Buttons(vec![
                 CreateButton::new("login_1")
                     .style(serenity::ButtonStyle::Primary)
                     .emoji('🚀')
-                    .label("Login"),
+                    .label(locale.t(Msg::LoginButton)),
                 CreateButton::new("membership_1")
                     .style(serenity::ButtonStyle::Secondary)
                     .emoji(serenity::ReactionType::Unicode("✈️".to_string()))
-                    .label("Membership"),
+                    .label(locale.t(Msg::MembershipButton)),
                 CreateButton::new("manual_1")
                     .style(serenity::ButtonStyle::Secondary)
                     .emoji('🚗')
-                    .label("Manual"),
+                    .label(locale.t(Msg::ManualButton)),
             ])]);
         m.create_response(
             &ctx.http,
@@ -124,42 +233,55 @@ pub(crate) async fn start(
 
 #[tracing::instrument(skip_all)]
 pub(crate) async fn apply_role(
-    ctx: &serenity::Context,
+    http: impl AsRef<serenity::Http>,
     member: &mut serenity::Member,
     role: serenity::RoleId,
 ) -> Result<(), Error> {
-    Ok(member.add_role(&ctx.http, role).await?)
+    Ok(member.add_role(http, role).await?)
 }
 
 #[tracing::instrument(skip_all)]
 pub(crate) async fn remove_role(
-    ctx: &serenity::Context,
+    http: impl AsRef<serenity::Http>,
     member: &mut serenity::Member,
     role: serenity::RoleId,
 ) -> Result<(), Error> {
-    Ok(member.remove_role(&ctx.http, role).await?)
+    Ok(member.remove_role(http, role).await?)
 }
 
 #[tracing::instrument(skip_all)]
 pub(crate) async fn welcome_user(
     http: impl CacheHttp,
-    channel: &serenity::ChannelId,
+    data: &Data,
     user: &serenity::User,
     fresher: Fresher,
+    locale: Locale,
 ) -> Result<(), Error> {
-    channel
-        .send_message(
-            http,
-            CreateMessage::new().content(format!(
-                "Welcome to ICAS {user}, if you have any questions, \
-                    feel free to ping a committee member{}!",
-                match fresher {
-                    Fresher::No => "",
-                    Fresher::YesPg => ", and look out for other postgraduate freshers in green",
-                    Fresher::YesUg => ", and look out for other freshers in green",
-                }
-            )),
-        )
+    let content = templates::welcome(data, user, fresher, locale).await?;
+    data.guild()
+        .gn_ch_id
+        .send_message(http, CreateMessage::new().content(content))
         .await?;
     Ok(())
 }
+
+/// Localised modal of required short text inputs, given as (field, label, placeholder)
+///
+/// Input custom IDs are the field names, so the submission parses with the derived `Modal`
+pub(crate) fn modal(
+    custom_id: String,
+    title: &str,
+    inputs: &[(&str, &str, &str)],
+) -> CreateInteractionResponse {
+    let components = inputs
+        .iter()
+        .map(|&(field, label, placeholder)| {
+            CreateActionRow::InputText(
+                CreateInputText::new(serenity::InputTextStyle::Short, label, field)
+                    .placeholder(placeholder)
+                    .required(true),
+            )
+        })
+        .collect();
+    CreateInteractionResponse::Modal(CreateModal::new(custom_id, title).components(components))
+}
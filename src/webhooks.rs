@@ -0,0 +1,181 @@
+use crate::{db, Error, Fresher, Method, WebhookDelivery};
+use hmac::{Hmac, Mac};
+use poise::serenity_prelude as serenity;
+use sha2::Sha256;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::Notify;
+
+/// Attempts before a delivery is marked as failed
+const MAX_ATTEMPTS: i64 = 8;
+/// Delay in seconds before the first retry, doubled after each failed attempt
+const BACKOFF: i64 = 30;
+/// Deliveries attempted per batch
+const BATCH: i64 = 20;
+/// Interval to check for due retries when not woken by a new event
+const POLL: Duration = Duration::from_secs(30);
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Membership state change sent to webhook endpoints
+#[derive(Debug, serde::Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum Event {
+    Verified {
+        discord_id: i64,
+        method: Method,
+        fresher: Fresher,
+    },
+    Denied {
+        discord_id: i64,
+    },
+    Gaijin {
+        discord_id: i64,
+        university: String,
+    },
+    Deleted {
+        discord_id: i64,
+    },
+}
+
+impl Event {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Event::Verified { .. } => "verified",
+            Event::Denied { .. } => "denied",
+            Event::Gaijin { .. } => "gaijin",
+            Event::Deleted { .. } => "deleted",
+        }
+    }
+
+    /// JSON body sent to endpoints, with the time of the change
+    pub(crate) fn payload(&self) -> Result<String, serde_json::Error> {
+        let mut payload = serde_json::to_value(self)?;
+        payload["occurred_at"] = serenity::Timestamp::now().unix_timestamp().into();
+        Ok(payload.to_string())
+    }
+}
+
+/// Sends signed JSON events to the endpoints in `WEBHOOK_URLS`, through a retry queue
+/// in the database
+///
+/// Events are recorded by the database functions making each change, in the same
+/// transaction, and queued for each endpoint by the delivery worker
+///
+/// Each request has the headers `X-Nano-Event`, `X-Nano-Delivery`, `X-Nano-Timestamp`
+/// and `X-Nano-Signature: <hex HMAC-SHA256 of "<timestamp>.<body>">`, keyed with
+/// `WEBHOOK_SECRET`
+#[derive(Clone)]
+pub(crate) struct Webhooks {
+    pool: sqlx::SqlitePool,
+    endpoints: Vec<String>,
+    secret: String,
+    http: reqwest::Client,
+    notify: Arc<Notify>,
+}
+
+impl Webhooks {
+    pub(crate) fn from_env(pool: sqlx::SqlitePool) -> Result<Self, Error> {
+        use crate::var;
+        use anyhow::Context as _;
+
+        let endpoints = std::env::var("WEBHOOK_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        for url in &endpoints {
+            url::Url::parse(url).context(format!("WEBHOOK_URLS has invalid URL {url}"))?;
+        }
+        let secret = if endpoints.is_empty() {
+            String::new()
+        } else {
+            var!("WEBHOOK_SECRET")
+        };
+        Ok(Self {
+            pool,
+            endpoints,
+            secret,
+            http: reqwest::Client::builder().timeout(TIMEOUT).build()?,
+            notify: Arc::new(Notify::new()),
+        })
+    }
+
+    pub(crate) fn enabled(&self) -> bool {
+        !self.endpoints.is_empty()
+    }
+
+    /// Wake the delivery worker, after a change records an event or deliveries are queued
+    /// for replay
+    pub(crate) fn wake(&self) {
+        self.notify.notify_one();
+    }
+
+    /// Deliver queued events until the program exits
+    pub(crate) async fn run(self) {
+        loop {
+            if let Err(e) = db::dispatch_webhook_events(&self.pool, &self.endpoints).await {
+                tracing::error!("Failed to queue webhook events: {e}");
+            }
+            let due = match db::get_due_webhook_deliveries(&self.pool, BATCH).await {
+                Ok(due) => due,
+                Err(e) => {
+                    tracing::error!("Failed to get webhook deliveries: {e}");
+                    vec![]
+                }
+            };
+            for d in &due {
+                self.attempt(d).await;
+            }
+            if i64::try_from(due.len()).is_ok_and(|n| n == BATCH) {
+                continue;
+            }
+            tokio::select! {
+                () = self.notify.notified() => {}
+                () = tokio::time::sleep(POLL) => {}
+            }
+        }
+    }
+
+    async fn attempt(&self, d: &WebhookDelivery) {
+        let result = match self.deliver(d).await {
+            Ok(()) => db::set_webhook_delivered(&self.pool, d.id).await,
+            Err(e) => {
+                let retry_in =
+                    (d.attempts + 1 < MAX_ATTEMPTS).then(|| BACKOFF << d.attempts.min(16));
+                if retry_in.is_none() {
+                    tracing::warn!("Webhook delivery {} to {} failed: {e}", d.id, d.endpoint);
+                }
+                db::set_webhook_attempt_failed(&self.pool, d.id, &e, retry_in).await
+            }
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to update webhook delivery {}: {e}", d.id);
+        }
+    }
+
+    async fn deliver(&self, d: &WebhookDelivery) -> Result<(), String> {
+        let timestamp = serenity::Timestamp::now().unix_timestamp();
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(format!("{timestamp}.{}", d.payload).as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+        let res = self
+            .http
+            .post(&d.endpoint)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Nano-Event", &d.event)
+            .header("X-Nano-Delivery", d.id)
+            .header("X-Nano-Timestamp", timestamp)
+            .header("X-Nano-Signature", signature)
+            .body(d.payload.clone())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if res.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("HTTP {}", res.status()))
+        }
+    }
+}